@@ -0,0 +1,37 @@
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+/// 根据扩展名判断是否是音视频文件
+pub fn is_media_file(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| {
+            let ext = ext.to_lowercase();
+            extensions.contains(&ext.as_str())
+        })
+        .unwrap_or(false)
+}
+
+/// 收集路径下的音视频文件
+///
+/// 如果是文件, 直接返回它; 如果是文件夹, 递归扫描其中的音视频文件, 按文件名自然排序 (2.mp4 在 10.mp4 之前)
+pub fn collect_media_files(path: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+
+    WalkDir::new(path)
+        .follow_links(true)
+        .sort_by(|a, b| {
+            natord::compare_ignore_case(
+                &a.file_name().to_string_lossy(),
+                &b.file_name().to_string_lossy(),
+            )
+        })
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_media_file(entry.path(), extensions))
+        .map(|entry| entry.into_path())
+        .collect()
+}
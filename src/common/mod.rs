@@ -1 +1,4 @@
+pub mod media;
+pub mod spectrum;
+pub mod toml_file;
 pub mod utils;
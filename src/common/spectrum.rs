@@ -0,0 +1,139 @@
+use std::f32::consts::PI;
+
+/// 频谱显示的频率范围(Hz)
+const MIN_FREQ: f32 = 40.0;
+const MAX_FREQ: f32 = 16000.0;
+/// 低于此值(dB)的频段显示为 0
+const MIN_DB: f32 = -70.0;
+
+/// 计算 samples 的频谱, 按对数频率分成 bands 个频段, 每个频段的强度在 0.0 ~ 1.0 之间
+///
+/// samples 的长度必须是 2 的幂
+pub fn spectrum(samples: &[f32], sample_rate: u32, bands: usize) -> Vec<f32> {
+    let n = samples.len();
+    if n < 2 || !n.is_power_of_two() || sample_rate == 0 || bands == 0 {
+        return vec![0.0; bands];
+    }
+
+    // 汉宁窗, 减少频谱泄漏
+    let mut buf: Vec<(f32, f32)> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let w = 0.5 - 0.5 * (2.0 * PI * i as f32 / (n - 1) as f32).cos();
+            (s * w, 0.0)
+        })
+        .collect();
+    fft(&mut buf);
+
+    // 只有前一半有意义, 乘以 4 补偿单边频谱和汉宁窗的衰减
+    let magnitudes: Vec<f32> = buf[..n / 2]
+        .iter()
+        .map(|(re, im)| (re * re + im * im).sqrt() * 4.0 / n as f32)
+        .collect();
+
+    let bin_width = sample_rate as f32 / n as f32;
+    let max_freq = MAX_FREQ.min(sample_rate as f32 / 2.0);
+    let ratio = (max_freq / MIN_FREQ).powf(1.0 / bands as f32);
+    (0..bands)
+        .map(|i| {
+            let low = MIN_FREQ * ratio.powi(i as i32);
+            let high = low * ratio;
+            let start = ((low / bin_width) as usize).min(magnitudes.len() - 1);
+            let end = ((high / bin_width).ceil() as usize).clamp(start + 1, magnitudes.len());
+            let magnitude = magnitudes[start..end].iter().fold(0.0f32, |a, b| a.max(*b));
+            let db = 20.0 * magnitude.max(1e-10).log10();
+            ((db - MIN_DB) / -MIN_DB).clamp(0.0, 1.0)
+        })
+        .collect()
+}
+
+/// 原地计算的基 2 快速傅里叶变换, 复数用 (实部, 虚部) 表示
+fn fft(buf: &mut [(f32, f32)]) {
+    let n = buf.len();
+
+    // 按位反转的顺序重新排列
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        let (w_re, w_im) = (angle.cos(), angle.sin());
+        for chunk in buf.chunks_mut(len) {
+            let (mut re, mut im) = (1.0f32, 0.0f32);
+            let (first, second) = chunk.split_at_mut(len / 2);
+            for (a, b) in first.iter_mut().zip(second.iter_mut()) {
+                let t = (b.0 * re - b.1 * im, b.0 * im + b.1 * re);
+                *b = (a.0 - t.0, a.1 - t.1);
+                *a = (a.0 + t.0, a.1 + t.1);
+                (re, im) = (re * w_re - im * w_im, re * w_im + im * w_re);
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 48000;
+    const BANDS: usize = 32;
+
+    fn sine(freq: f32, amplitude: f32) -> Vec<f32> {
+        (0..4096)
+            .map(|i| amplitude * (2.0 * PI * freq * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect()
+    }
+
+    /// freq 所在的频段, 低频的频段比 FFT 的分辨率窄, 测试时使用较高的频率
+    fn band_of(freq: f32) -> usize {
+        let ratio = (MAX_FREQ / MIN_FREQ).powf(1.0 / BANDS as f32);
+        ((freq / MIN_FREQ).ln() / ratio.ln()) as usize
+    }
+
+    #[test]
+    fn sine_peaks_in_its_band() {
+        for freq in [500.0, 1000.0, 5000.0] {
+            let bands = spectrum(&sine(freq, 0.5), SAMPLE_RATE, BANDS);
+            let peak = (0..BANDS)
+                .max_by(|a, b| bands[*a].partial_cmp(&bands[*b]).unwrap())
+                .unwrap();
+            assert_eq!(peak, band_of(freq), "{} Hz: {:?}", freq, bands);
+            assert!(bands[peak] > 0.8, "{} Hz: {:?}", freq, bands);
+        }
+    }
+
+    #[test]
+    fn sine_leaves_distant_bands_empty() {
+        let bands = spectrum(&sine(1000.0, 0.5), SAMPLE_RATE, BANDS);
+        let peak = band_of(1000.0);
+        assert_eq!(bands[peak - 8], 0.0);
+        assert_eq!(bands[peak + 8], 0.0);
+    }
+
+    #[test]
+    fn silence_is_all_zero() {
+        assert_eq!(spectrum(&[0.0; 4096], SAMPLE_RATE, BANDS), vec![0.0; BANDS]);
+    }
+
+    #[test]
+    fn invalid_input_is_all_zero() {
+        assert_eq!(
+            spectrum(&sine(1000.0, 0.5)[..1000], SAMPLE_RATE, BANDS),
+            vec![0.0; BANDS]
+        );
+        assert_eq!(spectrum(&sine(1000.0, 0.5), 0, BANDS), vec![0.0; BANDS]);
+    }
+}
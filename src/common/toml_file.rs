@@ -0,0 +1,26 @@
+use std::{fs, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// 读取 toml 文件, 文件不存在或者解析失败时返回 None
+pub fn load<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = fs::read_to_string(path).ok()?;
+    toml::from_str(&content)
+        .map_err(|e| log::error!("解析 {:?} 失败, E: {}", path, e))
+        .ok()
+}
+
+/// 保存到 toml 文件, 目录不存在时自动创建
+pub fn save<T: Serialize>(path: &Path, value: &T) {
+    let result = toml::to_string(value)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            }
+            fs::write(path, content).map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        log::error!("保存 {:?} 失败, E: {}", path, e);
+    }
+}
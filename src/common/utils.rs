@@ -31,3 +31,14 @@ impl<T> Clone for RcCell<T> {
         }
     }
 }
+
+/// 把秒数格式化成 "分:秒" 或者 "时:分:秒"
+pub fn format_duration(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{:02}:{:02}", m, s)
+    }
+}
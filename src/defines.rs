@@ -1,12 +1,26 @@
 use std::time::Duration;
 
 pub const APP_NAME: &str = "小小播放器";
+/// 配置和数据文件所在的目录名
+pub const APP_DIR_NAME: &str = "small-player";
+
+/*
+    缓冲区大小的默认值, 可以在设置中修改
+*/
 
 pub const AUDIO_FRAME_QUEUE_SIZE: usize = 5;
 pub const VIDEO_FRAME_QUEUE_SIZE: usize = 1;
+/// 每个流的 packet 队列最多占用的内存(字节)
+pub const PACKET_QUEUE_MAX_MEM: i32 = 16 * 1024 * 1024;
 
 pub const PLAY_MIN_INTERVAL: Duration = Duration::from_secs_f64(0.05);
 
+/// 保留最近播放的采样数, 用于音频可视化, 也是 FFT 的长度, 必须是 2 的幂
+pub const AUDIO_TAP_SIZE: usize = 2048;
+
+/// 检查音频设备是否断开的间隔
+pub const AUDIO_DEVICE_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
 /*
     音画同步阈值范围 参考: https://zhuanlan.zhihu.com/p/51924640
 */
@@ -15,6 +29,65 @@ pub const PLAY_MIN_INTERVAL: Duration = Duration::from_secs_f64(0.05);
 pub const VIDEO_SYNC_THRESHOLD_MIN: f64 = -0.1;
 /// 视频同步阈值最大 (视频领先于音频的最大时间)
 pub const VIDEO_SYNC_THRESHOLD_MAX: f64 = 0.025;
+/// 视频落后于音频超过此值(秒)时, 丢弃视频帧, 不再显示
+pub const VIDEO_DROP_THRESHOLD: f64 = 0.2;
+/// 最多连续丢弃的帧数, 保证画面偶尔还能刷新
+pub const VIDEO_MAX_CONSECUTIVE_DROPS: usize = 10;
+/// 视频落后于音频超过此值(秒)时, 解码器跳过非参考帧, 落后小于 VIDEO_DROP_THRESHOLD 后恢复
+pub const VIDEO_SKIP_THRESHOLD: f64 = 0.5;
+
+/*
+    快捷键
+*/
+
+/// 快进 / 快退 的秒数
+pub const SEEK_STEP: f64 = 5.0;
+/// 增大 / 减小 音量的步长
+pub const VOLUME_STEP: f32 = 0.05;
+/// 调节音频延迟的步长(秒)
+pub const AUDIO_DELAY_STEP: f64 = 0.01;
+/// 音频延迟的最大值(秒), 可以是 -AUDIO_DELAY_MAX ~ AUDIO_DELAY_MAX
+pub const AUDIO_DELAY_MAX: f64 = 5.0;
+/// 最大音量, 超过 1.0 的部分由软限幅避免削波
+pub const VOLUME_MAX: f32 = 2.0;
+/// 音量从 0 变化到 1.0 的过渡时间(秒), 避免音量突变产生爆音
+pub const VOLUME_RAMP_TIME: f32 = 0.03;
+
+/*
+    鼠标
+*/
+
+/// 两次单击的间隔小于此值(秒)时, 认为是双击
+pub const DOUBLE_CLICK_DELAY: f64 = 0.3;
+/// 滚轮每滚动这么多距离(egui 中滚动一行的距离), 调节一次音量或跳转
+pub const SCROLL_STEP: f32 = 50.0;
+/// 每次放大 / 缩小画面的倍数, 滚轮按滚动距离换算
+pub const ZOOM_STEP: f32 = 1.25;
+/// 画面最大的放大倍数
+pub const ZOOM_MAX: f32 = 16.0;
+/// 右键菜单中可选的播放速度
+pub const PLAYBACK_SPEEDS: [f64; 7] = [0.5, 0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+
+/// 画面上方提示的显示时间(秒)
+pub const NOTICE_DURATION: f64 = 3.0;
+
+/// 设置停止修改这么久之后, 才保存到配置文件, 避免拖动滑块时频繁写文件
+pub const SETTINGS_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/*
+    观看记录
+*/
+
+/// 时长小于此值(秒)的文件, 不记录播放位置
+pub const RESUME_MIN_DURATION: f64 = 60.0;
+/// 播放位置小于此值(秒)时, 不需要继续播放
+pub const RESUME_MIN_POSITION: f64 = 10.0;
+/// 距离结尾小于此值(秒)时, 认为已经播放完毕
+pub const RESUME_END_MARGIN: f64 = 15.0;
+/// 观看记录最多保存的条数
+pub const HISTORY_MAX_ENTRIES: usize = 500;
+/// 观看记录自动保存的间隔(秒)
+pub const HISTORY_SAVE_INTERVAL: u64 = 10;
 
 pub mod icons {
     pub const ICON_LOGO: &[u8] = include_bytes!("../misc/icons/logo.jpg");
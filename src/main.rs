@@ -5,10 +5,13 @@ use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*};
 use bevy_egui::EguiPlugin;
 
 use resources::event::PlayerEvent;
-use services::player::player::Player;
+use services::{history::WatchHistory, player::player::Player, settings::Settings};
 use system::{
-    event_system::update_event,
+    drop_system::update_drop,
+    event_system::{save_on_exit, update_event},
+    input_system::update_keyboard,
     play_system::{restart_player, start_player, stop_player, update_player},
+    setting_system::update_settings,
     setup_system::{egui_setup, icon_setup},
     ui_system::update_ui,
     GameState,
@@ -28,13 +31,17 @@ fn main() {
     if cfg!(target_os = "linux") {
         std::env::set_var("WGPU_BACKEND", "gl");
     }
+    let settings = Settings::load();
+
     App::new()
         .insert_resource(WindowDescriptor {
             decorations: false,
             ..Default::default()
         })
-        .init_resource::<UiState>()
+        .insert_resource(UiState::new(&settings))
+        .insert_resource(settings)
         .init_resource::<Player>()
+        .init_resource::<WatchHistory>()
         .add_state(GameState::Terminal)
         .add_event::<PlayerEvent>()
         .add_plugins(DefaultPlugins)
@@ -43,6 +50,10 @@ fn main() {
         .add_startup_system(icon_setup)
         .add_startup_system(egui_setup)
         .add_system(update_ui.chain(update_event))
+        .add_system_to_stage(CoreStage::Last, save_on_exit)
+        .add_system(update_drop)
+        .add_system(update_keyboard)
+        .add_system(update_settings)
         .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(start_player))
         .add_system_set(SystemSet::on_update(GameState::Playing).with_system(update_player))
         .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(stop_player))
@@ -0,0 +1,354 @@
+use bevy_egui::egui::{pos2, vec2, Pos2, Rect, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::defines::ZOOM_MAX;
+
+/// 视频画面在显示区域中的缩放方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScaleMode {
+    /// 完整显示画面, 多余的区域留黑边
+    #[default]
+    Fit,
+    /// 填满显示区域, 裁掉超出的部分
+    Fill,
+    /// 拉伸到显示区域的大小, 不保持宽高比
+    Stretch,
+    /// 一个视频像素对应一个屏幕像素
+    Original,
+}
+
+impl ScaleMode {
+    pub const ALL: [ScaleMode; 4] = [Self::Fit, Self::Fill, Self::Stretch, Self::Original];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Fit => "适应窗口",
+            Self::Fill => "填满窗口",
+            Self::Stretch => "拉伸",
+            Self::Original => "原始大小",
+        }
+    }
+}
+
+/// 强制的画面宽高比
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AspectRatio {
+    /// 使用视频的显示宽高比
+    #[default]
+    Auto,
+    Ratio4x3,
+    Ratio16x9,
+    Ratio235x1,
+}
+
+impl AspectRatio {
+    pub const ALL: [AspectRatio; 4] = [
+        Self::Auto,
+        Self::Ratio4x3,
+        Self::Ratio16x9,
+        Self::Ratio235x1,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "原始比例",
+            Self::Ratio4x3 => "4:3",
+            Self::Ratio16x9 => "16:9",
+            Self::Ratio235x1 => "2.35:1",
+        }
+    }
+
+    /// 宽 / 高, Auto 时为 None
+    pub fn ratio(&self) -> Option<f32> {
+        match self {
+            Self::Auto => None,
+            Self::Ratio4x3 => Some(4.0 / 3.0),
+            Self::Ratio16x9 => Some(16.0 / 9.0),
+            Self::Ratio235x1 => Some(2.35),
+        }
+    }
+}
+
+/// 顺时针旋转画面
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum Rotation {
+    #[default]
+    None,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+}
+
+impl Rotation {
+    pub const ALL: [Rotation; 4] = [Self::None, Self::Rotate90, Self::Rotate180, Self::Rotate270];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "不旋转",
+            Self::Rotate90 => "90°",
+            Self::Rotate180 => "180°",
+            Self::Rotate270 => "270°",
+        }
+    }
+
+    /// 顺时针旋转的角度
+    pub fn degrees(&self) -> f64 {
+        match self {
+            Self::None => 0.0,
+            Self::Rotate90 => 90.0,
+            Self::Rotate180 => 180.0,
+            Self::Rotate270 => 270.0,
+        }
+    }
+
+    /// 取最接近的 90° 的倍数
+    pub fn from_degrees(degrees: f64) -> Self {
+        match ((degrees / 90.0).round() as i64).rem_euclid(4) {
+            1 => Self::Rotate90,
+            2 => Self::Rotate180,
+            3 => Self::Rotate270,
+            _ => Self::None,
+        }
+    }
+}
+
+/// 没有视频时, 音频可视化的样式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VisualizerStyle {
+    /// 频谱柱状图
+    #[default]
+    Bars,
+    /// 频谱曲线
+    Curve,
+    /// 示波器, 显示波形
+    Oscilloscope,
+    /// 不显示
+    Off,
+}
+
+impl VisualizerStyle {
+    pub const ALL: [VisualizerStyle; 4] = [Self::Bars, Self::Curve, Self::Oscilloscope, Self::Off];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bars => "频谱柱",
+            Self::Curve => "频谱曲线",
+            Self::Oscilloscope => "示波器",
+            Self::Off => "关闭",
+        }
+    }
+}
+
+/// 计算画面在显示区域中的位置, 以及需要显示的纹理区域(uv)
+///
+/// - area: 显示区域
+/// - aspect_ratio: 画面的显示宽高比
+/// - original_size: Original 模式下画面的大小(点)
+pub fn video_layout(
+    area: Rect,
+    aspect_ratio: f32,
+    scale_mode: ScaleMode,
+    original_size: Vec2,
+) -> (Rect, Rect) {
+    let full_uv = Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0));
+    if area.width() <= 0.0 || area.height() <= 0.0 || aspect_ratio <= 0.0 {
+        return (area, full_uv);
+    }
+
+    let fit_width = vec2(area.width(), area.width() / aspect_ratio);
+    let fit_height = vec2(area.height() * aspect_ratio, area.height());
+    let size = match scale_mode {
+        ScaleMode::Stretch => return (area, full_uv),
+        ScaleMode::Fit if fit_width.y <= area.height() => fit_width,
+        ScaleMode::Fit => fit_height,
+        ScaleMode::Fill if fit_width.y >= area.height() => fit_width,
+        ScaleMode::Fill => fit_height,
+        ScaleMode::Original => original_size,
+    };
+
+    // 居中, 超出显示区域的部分裁掉
+    let rect = Rect::from_center_size(area.center(), size);
+    let visible = rect.intersect(area);
+    let uv = Rect::from_min_max(
+        pos2(
+            (visible.min.x - rect.min.x) / size.x,
+            (visible.min.y - rect.min.y) / size.y,
+        ),
+        pos2(
+            (visible.max.x - rect.min.x) / size.x,
+            (visible.max.y - rect.min.y) / size.y,
+        ),
+    );
+    (visible, uv)
+}
+
+/// 画面的放大和平移, 通过缩小纹理的显示区域(uv)实现
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoZoom {
+    /// 放大倍数, 1.0 表示不放大
+    level: f32,
+    /// 显示区域的中心(uv), None 时为画面中心
+    center: Option<Pos2>,
+}
+
+impl Default for VideoZoom {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            center: None,
+        }
+    }
+}
+
+impl VideoZoom {
+    pub fn is_zoomed(&self) -> bool {
+        self.level > 1.0
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 放大 factor 倍(小于 1 时缩小), anchor(uv) 处的画面保持不动, None 时以中心缩放
+    pub fn zoom(&mut self, factor: f32, anchor: Option<Pos2>) {
+        let level = (self.level * factor).clamp(1.0, ZOOM_MAX);
+        if level <= 1.0 {
+            self.reset();
+            return;
+        }
+        if let Some(anchor) = anchor {
+            let center = self.center();
+            self.center = Some(anchor + (center - anchor) * (self.level / level));
+        }
+        self.level = level;
+    }
+
+    /// 平移 delta(uv)
+    pub fn pan(&mut self, delta: Vec2) {
+        if self.is_zoomed() {
+            self.center = Some(self.center() + delta);
+        }
+    }
+
+    /// video_layout 的裁剪是居中的, 所以画面中心总是 uv 的 (0.5, 0.5)
+    fn center(&self) -> Pos2 {
+        self.center.unwrap_or_else(|| pos2(0.5, 0.5))
+    }
+
+    /// 在 video_layout 计算出的 uv 中, 取出放大后显示的部分
+    pub fn apply(&mut self, uv: Rect) -> Rect {
+        if !self.is_zoomed() {
+            return uv;
+        }
+        let size = uv.size() / self.level;
+        let half = size / 2.0;
+        // 不能移出画面
+        let center = self.center();
+        let center = pos2(
+            center.x.clamp(uv.min.x + half.x, uv.max.x - half.x),
+            center.y.clamp(uv.min.y + half.y, uv.max.y - half.y),
+        );
+        self.center = Some(center);
+        Rect::from_center_size(center, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn area() -> Rect {
+        Rect::from_min_size(pos2(0.0, 0.0), vec2(200.0, 100.0))
+    }
+
+    fn full_uv() -> Rect {
+        Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0))
+    }
+
+    fn assert_near(a: Pos2, b: Pos2) {
+        assert!((a - b).length() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn fit_keeps_whole_picture_centered() {
+        let (rect, uv) = video_layout(area(), 1.0, ScaleMode::Fit, Vec2::ZERO);
+        assert_eq!(
+            rect,
+            Rect::from_min_max(pos2(50.0, 0.0), pos2(150.0, 100.0))
+        );
+        assert_eq!(uv, full_uv());
+    }
+
+    #[test]
+    fn fill_crops_the_overflow() {
+        let (rect, uv) = video_layout(area(), 1.0, ScaleMode::Fill, Vec2::ZERO);
+        assert_eq!(rect, area());
+        assert_near(uv.min, pos2(0.0, 0.25));
+        assert_near(uv.max, pos2(1.0, 0.75));
+    }
+
+    #[test]
+    fn stretch_and_invalid_input_use_whole_area() {
+        let (rect, uv) = video_layout(area(), 1.0, ScaleMode::Stretch, Vec2::ZERO);
+        assert_eq!((rect, uv), (area(), full_uv()));
+        let (rect, uv) = video_layout(area(), 0.0, ScaleMode::Fit, Vec2::ZERO);
+        assert_eq!((rect, uv), (area(), full_uv()));
+    }
+
+    #[test]
+    fn original_size_larger_than_area_is_cropped() {
+        let size = vec2(400.0, 50.0);
+        let (rect, uv) = video_layout(area(), 8.0, ScaleMode::Original, size);
+        assert_eq!(rect, Rect::from_min_max(pos2(0.0, 25.0), pos2(200.0, 75.0)));
+        assert_near(uv.min, pos2(0.25, 0.0));
+        assert_near(uv.max, pos2(0.75, 1.0));
+    }
+
+    #[test]
+    fn zoom_at_anchor_keeps_anchor_still() {
+        let mut zoom = VideoZoom::default();
+        let anchor = pos2(0.25, 0.25);
+        zoom.zoom(2.0, Some(anchor));
+        let uv = zoom.apply(full_uv());
+        assert_near(uv.min, pos2(0.125, 0.125));
+        assert_near(uv.max, pos2(0.625, 0.625));
+
+        // anchor 一直显示在显示区域 1/4 的位置
+        zoom.zoom(2.0, Some(anchor));
+        let uv = zoom.apply(full_uv());
+        assert_near(uv.min, pos2(0.1875, 0.1875));
+        assert_near(uv.max, pos2(0.4375, 0.4375));
+    }
+
+    #[test]
+    fn zoom_without_anchor_uses_center() {
+        let mut zoom = VideoZoom::default();
+        zoom.zoom(4.0, None);
+        let uv = zoom.apply(full_uv());
+        assert_near(uv.center(), pos2(0.5, 0.5));
+        assert_near(uv.min, pos2(0.375, 0.375));
+    }
+
+    #[test]
+    fn pan_is_clamped_to_picture() {
+        let mut zoom = VideoZoom::default();
+        zoom.zoom(2.0, None);
+        zoom.pan(vec2(1.0, -0.1));
+        let uv = zoom.apply(full_uv());
+        assert_near(uv.min, pos2(0.5, 0.15));
+        assert_near(uv.max, pos2(1.0, 0.65));
+    }
+
+    #[test]
+    fn zoom_out_to_one_resets() {
+        let mut zoom = VideoZoom::default();
+        zoom.pan(vec2(0.1, 0.1));
+        assert_eq!(zoom, VideoZoom::default());
+
+        zoom.zoom(2.0, Some(pos2(0.9, 0.9)));
+        zoom.zoom(0.25, None);
+        assert!(!zoom.is_zoomed());
+        assert_eq!(zoom, VideoZoom::default());
+        assert_eq!(zoom.apply(full_uv()), full_uv());
+    }
+}
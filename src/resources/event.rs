@@ -1,7 +1,16 @@
+use crate::services::player::ColorAdjust;
+
+use super::{
+    display::{Rotation, VisualizerStyle},
+    playlist::RepeatMode,
+};
+
 #[derive(Debug)]
 pub enum PlayerEvent {
     /// 打开文件
     OpenFile,
+    /// 打开文件夹, 添加其中所有的音视频文件
+    OpenFolder,
 
     /*
         窗口
@@ -30,12 +39,38 @@ pub enum PlayerEvent {
     Play,
     /// 下一首
     Next,
-    /// 循环
-    Loop,
+    /// 当前文件播放完毕, 根据循环模式自动播放下一个
+    AutoNext,
+    /// 从上次的位置继续播放(true) 或者 从头播放(false)
+    Resume(bool),
+    /// 循环模式
+    Repeat(RepeatMode),
+    /// 随机播放
+    Shuffle(bool),
     /// 静音
     Mute(bool),
     /// 调节音量
     Volume(f32),
+    /// 快进(正数) / 快退(负数) 的秒数
+    Seek(f64),
+    /// 保存当前视频帧的截图
+    Screenshot,
+    /// 播放速度
+    Speed(f64),
+    /// 切换到指定的音轨(流索引)
+    AudioTrack(usize),
+    /// 手动旋转画面, None 时使用视频中记录的角度
+    Rotation(Option<Rotation>),
+    /// 音频可视化的样式
+    Visualizer(VisualizerStyle),
+    /// 色彩调节
+    Color(ColorAdjust),
+    /// 当前文件的音频延迟(秒), 正数表示声音比画面晚
+    AudioDelay(f64),
+    /// 画面在屏幕上显示的大小(像素)
+    DisplaySize(i32, i32),
+    /// 切换音频设备(名字), None 为默认设备
+    AudioDevice(Option<String>),
 
     /// 当前视频信息(video index, filename)
     Current(u32, String),
@@ -0,0 +1,505 @@
+use std::{collections::BTreeMap, fmt};
+
+use bevy::input::keyboard::KeyCode;
+use serde::{Deserialize, Serialize};
+
+/// 可以绑定快捷键的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    /// 播放 / 暂停
+    TogglePause,
+    SeekForward,
+    SeekBackward,
+    VolumeUp,
+    VolumeDown,
+    ToggleFullscreen,
+    /// 退出全屏
+    LeaveFullscreen,
+    ToggleMute,
+    Next,
+    Previous,
+    Screenshot,
+    ZoomIn,
+    ZoomOut,
+    /// 取消画面的放大和平移
+    ResetZoom,
+    /// 增大音频延迟, 声音延后
+    AudioDelayUp,
+    /// 减小音频延迟, 声音提前
+    AudioDelayDown,
+}
+
+impl Action {
+    pub const ALL: [Action; 16] = [
+        Self::TogglePause,
+        Self::SeekForward,
+        Self::SeekBackward,
+        Self::VolumeUp,
+        Self::VolumeDown,
+        Self::ToggleFullscreen,
+        Self::LeaveFullscreen,
+        Self::ToggleMute,
+        Self::Next,
+        Self::Previous,
+        Self::Screenshot,
+        Self::ZoomIn,
+        Self::ZoomOut,
+        Self::ResetZoom,
+        Self::AudioDelayUp,
+        Self::AudioDelayDown,
+    ];
+
+    /// 配置文件中使用的名字
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::TogglePause => "toggle_pause",
+            Self::SeekForward => "seek_forward",
+            Self::SeekBackward => "seek_backward",
+            Self::VolumeUp => "volume_up",
+            Self::VolumeDown => "volume_down",
+            Self::ToggleFullscreen => "toggle_fullscreen",
+            Self::LeaveFullscreen => "leave_fullscreen",
+            Self::ToggleMute => "toggle_mute",
+            Self::Next => "next",
+            Self::Previous => "previous",
+            Self::Screenshot => "screenshot",
+            Self::ZoomIn => "zoom_in",
+            Self::ZoomOut => "zoom_out",
+            Self::ResetZoom => "reset_zoom",
+            Self::AudioDelayUp => "audio_delay_up",
+            Self::AudioDelayDown => "audio_delay_down",
+        }
+    }
+
+    /// 界面上显示的名字
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TogglePause => "播放/暂停",
+            Self::SeekForward => "快进",
+            Self::SeekBackward => "快退",
+            Self::VolumeUp => "增大音量",
+            Self::VolumeDown => "减小音量",
+            Self::ToggleFullscreen => "全屏",
+            Self::LeaveFullscreen => "退出全屏",
+            Self::ToggleMute => "静音",
+            Self::Next => "下一首",
+            Self::Previous => "上一首",
+            Self::Screenshot => "截图",
+            Self::ZoomIn => "放大画面",
+            Self::ZoomOut => "缩小画面",
+            Self::ResetZoom => "重置放大",
+            Self::AudioDelayUp => "声音延后",
+            Self::AudioDelayDown => "声音提前",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    fn default_keys(&self) -> Vec<KeyBinding> {
+        let key = match self {
+            Self::TogglePause => KeyCode::Space,
+            Self::SeekForward => KeyCode::Right,
+            Self::SeekBackward => KeyCode::Left,
+            Self::VolumeUp => KeyCode::Up,
+            Self::VolumeDown => KeyCode::Down,
+            Self::ToggleFullscreen => KeyCode::F,
+            Self::LeaveFullscreen => KeyCode::Escape,
+            Self::ToggleMute => KeyCode::M,
+            Self::Next => KeyCode::N,
+            Self::Previous => KeyCode::P,
+            Self::Screenshot => KeyCode::S,
+            Self::ZoomIn => {
+                return vec![
+                    KeyBinding::new(KeyCode::Equals),
+                    KeyBinding::new(KeyCode::NumpadAdd),
+                ]
+            }
+            Self::ZoomOut => {
+                return vec![
+                    KeyBinding::new(KeyCode::Minus),
+                    KeyBinding::new(KeyCode::NumpadSubtract),
+                ]
+            }
+            Self::ResetZoom => KeyCode::Key0,
+            Self::AudioDelayUp => KeyCode::K,
+            Self::AudioDelayDown => KeyCode::J,
+        };
+        vec![KeyBinding::new(key)]
+    }
+}
+
+/// 按键组合, 例如 "Ctrl+Right"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    /// 修饰键本身不能作为快捷键
+    pub fn is_modifier(key: KeyCode) -> bool {
+        matches!(
+            key,
+            KeyCode::LControl
+                | KeyCode::RControl
+                | KeyCode::LShift
+                | KeyCode::RShift
+                | KeyCode::LAlt
+                | KeyCode::RAlt
+                | KeyCode::LWin
+                | KeyCode::RWin
+        )
+    }
+
+    /// 只有 SUPPORTED_KEYS 中的按键可以保存到配置文件
+    pub fn is_supported(key: KeyCode) -> bool {
+        SUPPORTED_KEYS.contains(&key)
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+        let key = parts.pop()?;
+        let key = SUPPORTED_KEYS
+            .into_iter()
+            .find(|code| format!("{:?}", code).eq_ignore_ascii_case(key))?;
+
+        let mut binding = Self::new(key);
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                _ => return None,
+            }
+        }
+        Some(binding)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// 快捷键设置, 在配置文件中保存为 操作 -> 按键列表, 例如:
+///
+/// ```toml
+/// [keymap]
+/// toggle_pause = ["Space"]
+/// seek_forward = ["Right", "Ctrl+F"]
+/// ```
+///
+/// 配置文件中没有的操作, 使用默认的按键
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    from = "BTreeMap<String, Vec<String>>",
+    into = "BTreeMap<String, Vec<String>>"
+)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<KeyBinding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| (action, action.default_keys()))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    pub fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings
+            .get(&action)
+            .map(|keys| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// 按键对应的操作, 有冲突时使用排在前面的操作
+    pub fn action(&self, binding: &KeyBinding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(binding))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn bind(&mut self, action: Action, binding: KeyBinding) {
+        let keys = self.bindings.entry(action).or_default();
+        if !keys.contains(&binding) {
+            keys.push(binding);
+        }
+    }
+
+    pub fn unbind(&mut self, action: Action, binding: &KeyBinding) {
+        if let Some(keys) = self.bindings.get_mut(&action) {
+            keys.retain(|key| key != binding);
+        }
+    }
+
+    /// 同一个按键绑定到其他操作上时, 返回这些操作
+    pub fn conflicts(&self, action: Action, binding: &KeyBinding) -> Vec<Action> {
+        self.bindings
+            .iter()
+            .filter(|(other, keys)| **other != action && keys.contains(binding))
+            .map(|(other, _)| *other)
+            .collect()
+    }
+}
+
+impl From<BTreeMap<String, Vec<String>>> for Keymap {
+    fn from(map: BTreeMap<String, Vec<String>>) -> Self {
+        let mut keymap = Self::default();
+        for (id, keys) in map {
+            let action = match Action::from_id(&id) {
+                Some(action) => action,
+                None => {
+                    log::warn!("未知的快捷键操作: {}", id);
+                    continue;
+                }
+            };
+            let keys = keys
+                .iter()
+                .filter_map(|key| {
+                    let binding = KeyBinding::parse(key);
+                    if binding.is_none() {
+                        log::warn!("无法识别的按键: {}", key);
+                    }
+                    binding
+                })
+                .collect();
+            keymap.bindings.insert(action, keys);
+        }
+        keymap
+    }
+}
+
+impl From<Keymap> for BTreeMap<String, Vec<String>> {
+    fn from(keymap: Keymap) -> Self {
+        keymap
+            .bindings
+            .into_iter()
+            .map(|(action, keys)| {
+                let keys = keys.iter().map(|key| key.to_string()).collect();
+                (action.id().to_string(), keys)
+            })
+            .collect()
+    }
+}
+
+/// 配置文件中可以使用的按键, 名字与 KeyCode 相同
+const SUPPORTED_KEYS: [KeyCode; 77] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Back,
+    KeyCode::Return,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Minus,
+    KeyCode::Equals,
+    KeyCode::Slash,
+    KeyCode::Backslash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::LBracket,
+    KeyCode::RBracket,
+    KeyCode::Grave,
+    KeyCode::Snapshot,
+    KeyCode::NumpadAdd,
+    KeyCode::NumpadSubtract,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pairs: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(id, keys)| {
+                let keys = keys.iter().map(|key| key.to_string()).collect();
+                (id.to_string(), keys)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_key_with_modifiers() {
+        let binding = KeyBinding::parse("ctrl + Shift+right").unwrap();
+        assert_eq!(binding.key, KeyCode::Right);
+        assert!(binding.ctrl && binding.shift && !binding.alt);
+
+        assert_eq!(
+            KeyBinding::parse("Space"),
+            Some(KeyBinding::new(KeyCode::Space))
+        );
+        assert_eq!(KeyBinding::parse(""), None);
+        assert_eq!(KeyBinding::parse("Hyper+A"), None);
+        assert_eq!(KeyBinding::parse("LControl"), None);
+    }
+
+    #[test]
+    fn only_supported_keys_can_be_saved() {
+        assert!(KeyBinding::is_supported(KeyCode::Space));
+        assert!(!KeyBinding::is_supported(KeyCode::LControl));
+        assert!(!KeyBinding::is_supported(KeyCode::Numpad5));
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for key in SUPPORTED_KEYS {
+            let binding = KeyBinding {
+                key,
+                ctrl: true,
+                shift: false,
+                alt: true,
+            };
+            assert_eq!(KeyBinding::parse(&binding.to_string()), Some(binding));
+            let binding = KeyBinding::new(key);
+            assert_eq!(KeyBinding::parse(&binding.to_string()), Some(binding));
+        }
+        let binding = KeyBinding {
+            key: KeyCode::F,
+            ctrl: true,
+            shift: true,
+            alt: true,
+        };
+        assert_eq!(binding.to_string(), "Ctrl+Shift+Alt+F");
+    }
+
+    #[test]
+    fn from_map_skips_unknown_actions_and_keys() {
+        let keymap = Keymap::from(keys(&[
+            ("toggle_pause", &["P", "NotAKey", "Ctrl+Space"]),
+            ("no_such_action", &["Q"]),
+        ]));
+
+        let mut ctrl_space = KeyBinding::new(KeyCode::Space);
+        ctrl_space.ctrl = true;
+        assert_eq!(
+            keymap.keys(Action::TogglePause),
+            [KeyBinding::new(KeyCode::P), ctrl_space]
+        );
+        // 配置中没有的操作保留默认按键
+        assert_eq!(
+            keymap.keys(Action::SeekForward),
+            [KeyBinding::new(KeyCode::Right)]
+        );
+        assert_eq!(keymap.action(&KeyBinding::new(KeyCode::Q)), None);
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let mut keymap = Keymap::default();
+        let mut binding = KeyBinding::new(KeyCode::Right);
+        binding.shift = true;
+        keymap.bind(Action::Next, binding);
+        let map: BTreeMap<String, Vec<String>> = keymap.clone().into();
+        assert_eq!(Keymap::from(map), keymap);
+    }
+
+    #[test]
+    fn conflicts_with_other_actions() {
+        let mut keymap = Keymap::default();
+        let space = KeyBinding::new(KeyCode::Space);
+        assert!(keymap.conflicts(Action::TogglePause, &space).is_empty());
+        assert_eq!(
+            keymap.conflicts(Action::Next, &space),
+            [Action::TogglePause]
+        );
+
+        keymap.bind(Action::Next, space);
+        assert_eq!(
+            keymap.conflicts(Action::Screenshot, &space),
+            [Action::TogglePause, Action::Next]
+        );
+        // 有冲突时使用排在前面的操作
+        assert_eq!(keymap.action(&space), Some(Action::TogglePause));
+
+        keymap.unbind(Action::TogglePause, &space);
+        assert_eq!(keymap.action(&space), Some(Action::Next));
+    }
+}
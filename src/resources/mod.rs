@@ -1,3 +1,6 @@
+pub mod display;
 pub mod event;
 pub mod fonts;
+pub mod keymap;
+pub mod playlist;
 pub mod theme;
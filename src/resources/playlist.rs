@@ -0,0 +1,478 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// 循环模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RepeatMode {
+    /// 不循环, 播放到列表末尾后停止
+    #[default]
+    Off,
+    /// 单曲循环
+    One,
+    /// 列表循环
+    All,
+}
+
+impl RepeatMode {
+    /// 切换到下一个循环模式: Off -> All -> One -> Off
+    pub fn cycle(self) -> Self {
+        match self {
+            Self::Off => Self::All,
+            Self::All => Self::One,
+            Self::One => Self::Off,
+        }
+    }
+}
+
+/// 播放列表条目的播放状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayItemState {
+    /// 未播放
+    Pending,
+    /// 正在播放
+    Playing,
+    /// 已播放
+    Played,
+    /// 播放失败
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayItem {
+    /// 文件路径
+    pub path: String,
+    /// 显示的标题, 默认是文件名
+    pub title: String,
+    /// 时长(秒), 播放后才能知道
+    pub duration: Option<f64>,
+    /// 播放状态
+    pub state: PlayItemState,
+    /// 随机播放时, 本轮是否已经播放过
+    shuffled: bool,
+}
+
+impl PlayItem {
+    pub fn new(path: impl Into<String>) -> Self {
+        let path = path.into();
+        let title = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        Self {
+            path,
+            title,
+            duration: None,
+            state: PlayItemState::Pending,
+            shuffled: false,
+        }
+    }
+}
+
+/// 播放列表
+///
+/// current 是当前选中(或正在播放)的条目, history 记录了之前播放过的条目,
+/// 随机播放时, "上一首" 会回到真正播放过的上一个条目.
+#[derive(Debug, Default)]
+pub struct Playlist {
+    items: Vec<PlayItem>,
+    current: Option<usize>,
+    repeat: RepeatMode,
+    shuffle: bool,
+    /// 播放历史, 最后一个是上一首
+    history: Vec<usize>,
+    /// 点击 "上一首" 后, 被回退的条目, 再点击 "下一首" 时, 优先从这里取
+    future: Vec<usize>,
+    /// 随机数状态 (xorshift)
+    seed: u64,
+}
+
+impl Playlist {
+    pub fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or_default();
+        Self {
+            seed: seed | 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_paths(paths: impl IntoIterator<Item = String>) -> Self {
+        let mut playlist = Self::new();
+        for path in paths {
+            playlist.push(path);
+        }
+        playlist
+    }
+
+    pub fn items(&self) -> &[PlayItem] {
+        &self.items
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn push(&mut self, path: impl Into<String>) {
+        self.items.push(PlayItem::new(path));
+    }
+
+    /// 移除一个条目, 同时修正 当前索引 和 历史记录
+    pub fn remove(&mut self, idx: usize) -> Option<PlayItem> {
+        if idx >= self.items.len() {
+            return None;
+        }
+        let item = self.items.remove(idx);
+
+        let fix = |i: usize| -> Option<usize> {
+            match i {
+                i if i == idx => None,
+                i if i > idx => Some(i - 1),
+                i => Some(i),
+            }
+        };
+        self.current = self.current.and_then(fix);
+        self.history = self.history.iter().filter_map(|i| fix(*i)).collect();
+        self.future = self.future.iter().filter_map(|i| fix(*i)).collect();
+        Some(item)
+    }
+
+    pub fn current_idx(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn current(&self) -> Option<&PlayItem> {
+        self.current.and_then(|idx| self.items.get(idx))
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut PlayItem> {
+        self.current.and_then(move |idx| self.items.get_mut(idx))
+    }
+
+    pub fn repeat(&self) -> RepeatMode {
+        self.repeat
+    }
+
+    pub fn set_repeat(&mut self, repeat: RepeatMode) {
+        self.repeat = repeat;
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        if self.shuffle != shuffle {
+            self.shuffle = shuffle;
+            self.future.clear();
+            self.reset_shuffle_round();
+        }
+    }
+
+    /// 选中一个条目, 下次 play 时播放它
+    pub fn select(&mut self, idx: usize) {
+        if idx >= self.items.len() || self.current == Some(idx) {
+            return;
+        }
+        self.move_to(idx);
+        self.future.clear();
+    }
+
+    /// 当前选中的条目, 如果没有选中, 则选中第一个(随机播放时, 随机选一个)
+    pub fn play(&mut self) -> Option<String> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let idx = match self.current {
+            Some(idx) => idx,
+            None => {
+                let idx = if self.shuffle {
+                    self.random_unplayed()?
+                } else {
+                    0
+                };
+                self.current = Some(idx);
+                idx
+            }
+        };
+        self.items[idx].shuffled = true;
+        Some(self.items[idx].path.clone())
+    }
+
+    /// 手动切换到下一首, 单曲循环时 和 列表循环一样, 到达末尾后回到开头
+    pub fn next(&mut self) -> Option<String> {
+        let wrap = self.repeat != RepeatMode::Off;
+        self.advance(wrap)
+    }
+
+    /// 当前文件播放完毕后, 根据循环模式自动选择下一首
+    pub fn auto_next(&mut self) -> Option<String> {
+        match self.repeat {
+            RepeatMode::One if self.current.is_some() => self.play(),
+            repeat => self.advance(repeat == RepeatMode::All),
+        }
+    }
+
+    /// 切换到上一首, 优先回到播放历史中的上一个条目
+    pub fn previous(&mut self) -> Option<String> {
+        let current = match self.current {
+            Some(idx) => idx,
+            None => return self.play(),
+        };
+
+        let idx = if let Some(idx) = self.history.pop() {
+            idx
+        } else if self.shuffle {
+            return None;
+        } else if current > 0 {
+            current - 1
+        } else if self.repeat != RepeatMode::Off {
+            self.items.len() - 1
+        } else {
+            return None;
+        };
+
+        self.future.push(current);
+        self.current = Some(idx);
+        Some(self.items[idx].path.clone())
+    }
+
+    /// 设置当前条目的播放状态, 进入播放状态时, 其它正在播放的条目变为已播放
+    pub fn set_current_state(&mut self, state: PlayItemState) {
+        if state == PlayItemState::Playing {
+            for item in self.items.iter_mut() {
+                if item.state == PlayItemState::Playing {
+                    item.state = PlayItemState::Played;
+                }
+            }
+        }
+        if let Some(item) = self.current_mut() {
+            item.state = state;
+        }
+    }
+
+    /// 停止播放, 正在播放的条目变为已播放
+    ///
+    /// 正在播放的条目不一定是当前条目, 例如切换到下一个文件后, 还在询问是否继续播放
+    pub fn stop_playing(&mut self) {
+        for item in self.items.iter_mut() {
+            if item.state == PlayItemState::Playing {
+                item.state = PlayItemState::Played;
+            }
+        }
+    }
+
+    fn advance(&mut self, wrap: bool) -> Option<String> {
+        let current = match self.current {
+            Some(idx) => idx,
+            None => return self.play(),
+        };
+
+        let idx = if let Some(idx) = self.future.pop() {
+            idx
+        } else if self.shuffle {
+            match self.random_unplayed() {
+                Some(idx) => idx,
+                None if wrap => {
+                    self.reset_shuffle_round();
+                    self.random_unplayed()?
+                }
+                None => return None,
+            }
+        } else if current + 1 < self.items.len() {
+            current + 1
+        } else if wrap {
+            0
+        } else {
+            return None;
+        };
+
+        self.move_to(idx);
+        self.items[idx].shuffled = true;
+        Some(self.items[idx].path.clone())
+    }
+
+    fn move_to(&mut self, idx: usize) {
+        if let Some(current) = self.current {
+            self.history.push(current);
+        }
+        self.current = Some(idx);
+    }
+
+    fn reset_shuffle_round(&mut self) {
+        for item in self.items.iter_mut() {
+            item.shuffled = false;
+        }
+        // 当前条目算作本轮已播放
+        if let Some(item) = self.current_mut() {
+            item.shuffled = true;
+        }
+    }
+
+    /// 随机选择一个本轮还没有播放过的条目
+    fn random_unplayed(&mut self) -> Option<usize> {
+        let candidates: Vec<usize> = (0..self.items.len())
+            .filter(|idx| !self.items[*idx].shuffled)
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        let n = self.next_random() as usize % candidates.len();
+        Some(candidates[n])
+    }
+
+    fn next_random(&mut self) -> u64 {
+        // xorshift64
+        let mut x = self.seed.max(1);
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed = x;
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn playlist(n: usize) -> Playlist {
+        Playlist::from_paths((0..n).map(|i| i.to_string()))
+    }
+
+    fn idx(path: Option<String>) -> Option<usize> {
+        path.map(|p| p.parse().unwrap())
+    }
+
+    #[test]
+    fn play_selects_first_item() {
+        let mut list = playlist(3);
+        assert_eq!(idx(list.play()), Some(0));
+        assert_eq!(list.current_idx(), Some(0));
+        assert_eq!(list.current().unwrap().title, "0");
+        assert_eq!(Playlist::new().play(), None);
+    }
+
+    #[test]
+    fn sequential_without_repeat_stops_at_edges() {
+        let mut list = playlist(3);
+        list.play();
+        assert_eq!(list.previous(), None);
+        assert_eq!(idx(list.next()), Some(1));
+        assert_eq!(idx(list.next()), Some(2));
+        assert_eq!(list.next(), None);
+        assert_eq!(list.auto_next(), None);
+        assert_eq!(list.current_idx(), Some(2));
+    }
+
+    #[test]
+    fn repeat_all_wraps_in_both_directions() {
+        let mut list = playlist(3);
+        list.set_repeat(RepeatMode::All);
+        list.play();
+        // 第一首的上一首是最后一首
+        assert_eq!(idx(list.previous()), Some(2));
+        assert_eq!(idx(list.auto_next()), Some(0));
+        list.select(2);
+        assert_eq!(idx(list.auto_next()), Some(0));
+    }
+
+    #[test]
+    fn repeat_one_replays_only_on_auto_next() {
+        let mut list = playlist(3);
+        list.set_repeat(RepeatMode::One);
+        list.play();
+        assert_eq!(idx(list.auto_next()), Some(0));
+        assert_eq!(idx(list.next()), Some(1));
+        list.select(2);
+        assert_eq!(idx(list.next()), Some(0));
+    }
+
+    #[test]
+    fn previous_follows_history() {
+        let mut list = playlist(5);
+        list.play();
+        list.select(3);
+        list.select(1);
+        assert_eq!(idx(list.previous()), Some(3));
+        assert_eq!(idx(list.previous()), Some(0));
+        // 回退之后, 下一首回到原来的位置
+        assert_eq!(idx(list.next()), Some(3));
+        assert_eq!(idx(list.next()), Some(1));
+    }
+
+    #[test]
+    fn shuffle_visits_every_item_once_per_round() {
+        let mut list = playlist(6);
+        list.set_shuffle(true);
+        let mut order = vec![idx(list.play()).unwrap()];
+        while let Some(i) = idx(list.next()) {
+            order.push(i);
+        }
+        let mut sorted = order.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..6).collect::<Vec<_>>());
+
+        // 上一首 按实际播放顺序回退
+        for expected in order.iter().rev().skip(1) {
+            assert_eq!(idx(list.previous()), Some(*expected));
+        }
+        assert_eq!(list.previous(), None);
+    }
+
+    #[test]
+    fn shuffle_with_repeat_all_starts_a_new_round() {
+        let mut list = playlist(3);
+        list.set_shuffle(true);
+        list.set_repeat(RepeatMode::All);
+        list.play();
+        for _ in 0..10 {
+            assert!(list.next().is_some());
+        }
+    }
+
+    #[test]
+    fn remove_fixes_current_and_history() {
+        let mut list = playlist(4);
+        list.play();
+        list.select(2);
+        list.select(3);
+        list.remove(2);
+        assert_eq!(list.current_idx(), Some(2));
+        assert_eq!(list.current().unwrap().path, "3");
+        assert_eq!(idx(list.previous()), Some(0));
+
+        list.remove(0);
+        assert_eq!(list.current_idx(), None);
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn playing_state_is_exclusive() {
+        let mut list = playlist(2);
+        list.play();
+        list.set_current_state(PlayItemState::Playing);
+        list.next();
+        list.set_current_state(PlayItemState::Playing);
+        let states: Vec<_> = list.items().iter().map(|i| i.state).collect();
+        assert_eq!(states, [PlayItemState::Played, PlayItemState::Playing]);
+    }
+
+    #[test]
+    fn stop_playing_marks_previous_item_played() {
+        let mut list = playlist(2);
+        list.play();
+        list.set_current_state(PlayItemState::Playing);
+        list.next();
+        list.stop_playing();
+        let states: Vec<_> = list.items().iter().map(|i| i.state).collect();
+        assert_eq!(states, [PlayItemState::Played, PlayItemState::Pending]);
+    }
+}
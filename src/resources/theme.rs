@@ -1,122 +1,431 @@
-use std::sync::Arc;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use bevy_egui::egui::{style::Margin, vec2, Color32, Rounding, Style, Visuals};
+use serde::{Deserialize, Serialize};
 
-/// 此处定义了两种样式
-pub struct Theme {
-    /// 暗黑风格
-    dark_style: Arc<Style>,
-    /// 明亮风格
-    light_style: Arc<Style>,
-    /// 蓝色风格
-    blue_style: Arc<Style>,
-    blue_titlebar_style: Arc<Style>,
-    blue_video_content_style: Arc<Style>,
+use crate::defines::APP_DIR_NAME;
+
+/// 默认主题的名字
+pub const DEFAULT_THEME: &str = "蓝色";
+/// 内置主题的数量
+const BUILTIN_THEMES: usize = 3;
+
+/// 主题的基础风格, 没有设置的颜色使用基础风格的颜色
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum BaseVisuals {
+    Dark,
+    #[default]
+    Light,
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        let dark_style = Style {
-            visuals: Visuals::dark(),
+/// 主题文件(toml 或者 json)描述的颜色, 颜色格式为 "#rrggbb" 或者 "#rrggbbaa"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ThemeColors {
+    pub name: String,
+    pub base: BaseVisuals,
+
+    /*
+        无法交互的元素
+    */
+    /// 窗口背景色
+    #[serde(with = "hex_color")]
+    pub window_bg: Option<Color32>,
+    /// 窗口边框颜色
+    #[serde(with = "hex_color")]
+    pub window_border: Option<Color32>,
+    /// 字体颜色
+    #[serde(with = "hex_color")]
+    pub text: Option<Color32>,
+
+    /*
+        可交互: 未激活时
+    */
+    #[serde(with = "hex_color")]
+    pub inactive_text: Option<Color32>,
+    #[serde(with = "hex_color")]
+    pub inactive_bg: Option<Color32>,
+
+    /*
+        可交互: 鼠标经过时
+    */
+    #[serde(with = "hex_color")]
+    pub hovered_text: Option<Color32>,
+    #[serde(with = "hex_color")]
+    pub hovered_bg: Option<Color32>,
+
+    /*
+        可交互: 激活时
+    */
+    #[serde(with = "hex_color")]
+    pub active_text: Option<Color32>,
+    #[serde(with = "hex_color")]
+    pub active_bg: Option<Color32>,
+
+    /// 视频区域的按钮背景色
+    #[serde(with = "hex_color")]
+    pub content_button_bg: Option<Color32>,
+    /// 底部控制栏背景色
+    #[serde(with = "hex_color")]
+    pub control_bar_bg: Option<Color32>,
+
+    /// 窗口的角半径
+    pub window_rounding: Option<f32>,
+    /// 滚动条宽度
+    pub scroll_bar_width: Option<f32>,
+}
+
+impl ThemeColors {
+    pub fn dark() -> Self {
+        Self {
+            name: "暗黑".to_string(),
+            base: BaseVisuals::Dark,
             ..Default::default()
-        };
+        }
+    }
 
-        let light_style = Style {
-            visuals: Visuals::light(),
+    pub fn light() -> Self {
+        Self {
+            name: "明亮".to_string(),
+            base: BaseVisuals::Light,
             ..Default::default()
+        }
+    }
+
+    pub fn blue() -> Self {
+        Self {
+            name: DEFAULT_THEME.to_string(),
+            base: BaseVisuals::Light,
+            window_bg: Some(Color32::from_rgb(3, 45, 100)),
+            window_border: Some(Color32::from_rgba_premultiplied(46, 46, 46, 0)),
+            text: Some(Color32::WHITE),
+            inactive_text: Some(Color32::from_rgb(205, 205, 205)),
+            inactive_bg: Some(Color32::from_rgb(25, 66, 124)),
+            hovered_text: Some(Color32::WHITE),
+            hovered_bg: Some(Color32::from_rgb(4, 148, 210)),
+            active_text: Some(Color32::from_rgb(205, 205, 205)),
+            active_bg: Some(Color32::from_rgb(37, 95, 226)),
+            content_button_bg: Some(Color32::from_rgb(65, 105, 178)),
+            control_bar_bg: Some(Color32::from_rgb(42, 56, 115)),
+            window_rounding: Some(0.0),
+            scroll_bar_width: Some(2.0),
+        }
+    }
+
+    /// 从 toml 或者 json 文件读取, 根据扩展名判断格式
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut colors: Self = if is_json(path) {
+            serde_json::from_str(&content).map_err(|e| e.to_string())?
+        } else {
+            toml::from_str(&content).map_err(|e| e.to_string())?
         };
+        if colors.name.is_empty() {
+            colors.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        Ok(colors)
+    }
 
-        let mut blue_style = Style {
-            visuals: Visuals::light(),
+    /// 保存到 toml 或者 json 文件, 根据扩展名判断格式
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = if is_json(path) {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        } else {
+            toml::to_string(self).map_err(|e| e.to_string())?
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, content).map_err(|e| e.to_string())
+    }
+
+    /// 根据颜色生成主样式
+    fn style(&self) -> Style {
+        let mut style = Style {
+            visuals: match self.base {
+                BaseVisuals::Dark => Visuals::dark(),
+                BaseVisuals::Light => Visuals::light(),
+            },
             ..Default::default()
         };
+        let widgets = &mut style.visuals.widgets;
 
-        /*
-            无法交互的元素
-        */
-
-        // 窗口背景色
-        blue_style.visuals.widgets.noninteractive.bg_fill = Color32::from_rgb(3, 45, 100);
-        // 窗口边框颜色
-        blue_style.visuals.widgets.noninteractive.bg_stroke.color =
-            Color32::from_rgba_premultiplied(46, 46, 46, 0);
-        // 字体颜色
-        blue_style.visuals.widgets.noninteractive.fg_stroke.color = Color32::WHITE;
-
-        /*
-            可交互: 未激活时
-        */
-
-        // 字体颜色
-        blue_style.visuals.widgets.inactive.fg_stroke.color = Color32::from_rgb(205, 205, 205);
-        // 按钮背景色
-        blue_style.visuals.widgets.inactive.bg_fill = Color32::from_rgb(25, 66, 124);
-
-        /*
-            可交互: 鼠标经过时
-        */
-
-        // 字体颜色
-        blue_style.visuals.widgets.hovered.fg_stroke.color = Color32::WHITE;
-        // 按钮背景颜色
-        blue_style.visuals.widgets.hovered.bg_fill = Color32::from_rgb(4, 148, 210);
-
-        /*
-            可交互: 激活时
-        */
-
-        // 字体颜色
-        blue_style.visuals.widgets.active.fg_stroke.color = Color32::WHITE;
-        // 背景颜色
-        blue_style.visuals.widgets.active.bg_fill = Color32::from_rgb(37, 95, 226);
-        // 未激活时 字体颜色
-        blue_style.visuals.widgets.active.fg_stroke.color = Color32::from_rgb(205, 205, 205);
-
-        // 设置窗口的角半径
-        blue_style.visuals.window_rounding = Rounding::from(0.0);
-        // 滚动条宽度
-        blue_style.spacing.scroll_bar_width = 2.;
-
-        let mut blue_titlebar_style = blue_style.clone();
-        // 按钮内间距
-        blue_titlebar_style.spacing.button_padding = vec2(5.0, 3.0);
-        // 组件间隔
-        blue_titlebar_style.spacing.item_spacing = vec2(5.0, 0.0);
+        let slots = [
+            (&mut widgets.noninteractive.bg_fill, self.window_bg),
+            (
+                &mut widgets.noninteractive.bg_stroke.color,
+                self.window_border,
+            ),
+            (&mut widgets.noninteractive.fg_stroke.color, self.text),
+            (&mut widgets.inactive.fg_stroke.color, self.inactive_text),
+            (&mut widgets.inactive.bg_fill, self.inactive_bg),
+            (&mut widgets.hovered.fg_stroke.color, self.hovered_text),
+            (&mut widgets.hovered.bg_fill, self.hovered_bg),
+            (&mut widgets.active.fg_stroke.color, self.active_text),
+            (&mut widgets.active.bg_fill, self.active_bg),
+        ];
+        for (slot, color) in slots {
+            if let Some(color) = color {
+                *slot = color;
+            }
+        }
 
-        let mut blue_video_content_style = blue_style.clone();
-        blue_video_content_style.spacing.window_margin = Margin::same(0.);
-        blue_video_content_style.spacing.button_padding = vec2(0., 0.);
-        blue_video_content_style.spacing.item_spacing = vec2(0., 0.);
-        blue_video_content_style.visuals.widgets.inactive.bg_fill = Color32::from_rgb(65, 105, 178);
+        if let Some(rounding) = self.window_rounding {
+            style.visuals.window_rounding = Rounding::from(rounding);
+        }
+        if let Some(width) = self.scroll_bar_width {
+            style.spacing.scroll_bar_width = width;
+        }
+        style
+    }
+}
 
-        Self {
-            dark_style: Arc::new(dark_style),
-            light_style: Arc::new(light_style),
-            blue_style: Arc::new(blue_style),
-            blue_titlebar_style: Arc::new(blue_titlebar_style),
-            blue_video_content_style: Arc::new(blue_video_content_style),
+/// 一个主题的各个区域的样式
+pub struct Theme {
+    /// 所有可选的主题, 内置主题在前, 然后是用户主题
+    themes: Vec<ThemeColors>,
+    /// 当前主题在 themes 中的位置
+    current: usize,
+
+    style: Arc<Style>,
+    titlebar_style: Arc<Style>,
+    video_content_style: Arc<Style>,
+    control_bar_bg: Color32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let mut themes = vec![
+            ThemeColors::blue(),
+            ThemeColors::dark(),
+            ThemeColors::light(),
+        ];
+
+        // 加载用户主题, 与已有主题重名的忽略
+        for path in user_theme_files() {
+            match ThemeColors::load(&path) {
+                Ok(colors) if themes.iter().any(|theme| theme.name == colors.name) => {
+                    log::warn!("主题 {:?} 与已有主题重名: {}", path, colors.name);
+                }
+                Ok(colors) => themes.push(colors),
+                Err(e) => log::error!("加载主题 {:?} 失败, E: {}", path, e),
+            }
         }
+
+        let mut theme = Self {
+            themes,
+            current: 0,
+            style: Default::default(),
+            titlebar_style: Default::default(),
+            video_content_style: Default::default(),
+            control_bar_bg: Color32::TRANSPARENT,
+        };
+        theme.rebuild();
+        theme
     }
 }
 
 impl Theme {
-    pub fn dark_style_clone(&self) -> Arc<Style> {
-        self.dark_style.clone()
+    /// 所有主题的名字
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.themes.iter().map(|theme| theme.name.as_str())
+    }
+
+    pub fn current(&self) -> &ThemeColors {
+        &self.themes[self.current]
+    }
+
+    /// 切换主题, 主题不存在时返回 false
+    pub fn select(&mut self, name: &str) -> bool {
+        match self.themes.iter().position(|theme| theme.name == name) {
+            Some(idx) => {
+                self.current = idx;
+                self.rebuild();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 修改当前主题的颜色
+    pub fn set_colors(&mut self, colors: ThemeColors) {
+        self.themes[self.current] = colors;
+        self.rebuild();
+    }
+
+    /// 添加主题 (同名的会被替换), 并切换到这个主题
+    pub fn add(&mut self, colors: ThemeColors) {
+        match self
+            .themes
+            .iter()
+            .position(|theme| theme.name == colors.name)
+        {
+            Some(idx) => {
+                self.themes[idx] = colors;
+                self.current = idx;
+            }
+            None => {
+                self.themes.push(colors);
+                self.current = self.themes.len() - 1;
+            }
+        }
+        self.rebuild();
+    }
+
+    /// 保存到用户主题目录, 并添加到主题列表中
+    pub fn save_user_theme(&mut self, colors: ThemeColors) -> Result<(), String> {
+        let dir = user_theme_dir().ok_or("无法获取配置目录")?;
+        let file_name = theme_file_name(&colors.name).ok_or("主题名无效")?;
+        colors.save(&dir.join(format!("{}.toml", file_name)))?;
+        self.add(colors);
+        Ok(())
+    }
+
+    /// 是否是内置主题
+    pub fn is_builtin(&self, name: &str) -> bool {
+        self.themes[..BUILTIN_THEMES]
+            .iter()
+            .any(|theme| theme.name == name)
+    }
+
+    pub fn style_clone(&self) -> Arc<Style> {
+        self.style.clone()
+    }
+
+    pub fn titlebar_style_clone(&self) -> Arc<Style> {
+        self.titlebar_style.clone()
     }
 
-    pub fn light_style_clone(&self) -> Arc<Style> {
-        self.light_style.clone()
+    pub fn video_content_style(&self) -> Arc<Style> {
+        self.video_content_style.clone()
     }
 
-    pub fn blue_style_clone(&self) -> Arc<Style> {
-        self.blue_style.clone()
+    /// 底部控制栏的背景色
+    pub fn control_bar_bg(&self) -> Color32 {
+        self.control_bar_bg
     }
 
-    pub fn blue_titlebar_style_clone(&self) -> Arc<Style> {
-        self.blue_titlebar_style.clone()
+    /// 根据当前主题的颜色, 重新生成各个区域的样式
+    fn rebuild(&mut self) {
+        let colors = &self.themes[self.current];
+        let style = colors.style();
+
+        let mut titlebar_style = style.clone();
+        // 按钮内间距
+        titlebar_style.spacing.button_padding = vec2(5.0, 3.0);
+        // 组件间隔
+        titlebar_style.spacing.item_spacing = vec2(5.0, 0.0);
+
+        let mut video_content_style = style.clone();
+        video_content_style.spacing.window_margin = Margin::same(0.);
+        video_content_style.spacing.button_padding = vec2(0., 0.);
+        video_content_style.spacing.item_spacing = vec2(0., 0.);
+        if let Some(color) = colors.content_button_bg {
+            video_content_style.visuals.widgets.inactive.bg_fill = color;
+        }
+
+        self.control_bar_bg = colors
+            .control_bar_bg
+            .unwrap_or(style.visuals.widgets.noninteractive.bg_fill);
+        self.style = Arc::new(style);
+        self.titlebar_style = Arc::new(titlebar_style);
+        self.video_content_style = Arc::new(video_content_style);
+    }
+}
+
+/// 主题名对应的文件名, 去掉路径分隔符和开头的 ".", 保证文件在主题目录中, 为空时返回 None
+fn theme_file_name(name: &str) -> Option<String> {
+    let file_name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let file_name = file_name.trim().trim_start_matches('.');
+    (!file_name.is_empty()).then(|| file_name.to_string())
+}
+
+/// 用户主题目录: $XDG_CONFIG_HOME/small-player/themes
+fn user_theme_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME).join("themes"))
+}
+
+/// 用户主题目录中的 toml 和 json 文件, 按文件名排序
+fn user_theme_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = user_theme_dir()
+        .and_then(|dir| fs::read_dir(dir).ok())
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.extension()
+                        .map(|ext| ext == "toml" || ext == "json")
+                        .unwrap_or(false)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+/// 颜色与 "#rrggbb" / "#rrggbbaa" 格式的转换, 使用预乘 alpha 的分量
+mod hex_color {
+    use bevy_egui::egui::Color32;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Option<Color32>, s: S) -> Result<S::Ok, S::Error> {
+        match color {
+            Some(color) => {
+                let [r, g, b, a] = color.to_array();
+                let hex = if a == 255 {
+                    format!("#{:02x}{:02x}{:02x}", r, g, b)
+                } else {
+                    format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+                };
+                s.serialize_str(&hex)
+            }
+            None => s.serialize_none(),
+        }
     }
 
-    pub fn blue_video_content_style(&self) -> Arc<Style> {
-        self.blue_video_content_style.clone()
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Color32>, D::Error> {
+        let hex = match Option::<String>::deserialize(d)? {
+            Some(hex) => hex,
+            None => return Ok(None),
+        };
+        let digits = hex.trim_start_matches('#');
+        let invalid = || D::Error::custom(format!("无效的颜色: {}", hex));
+        if !matches!(digits.len(), 6 | 8) {
+            return Err(invalid());
+        }
+        let channel = |i: usize| {
+            digits
+                .get(i * 2..i * 2 + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .ok_or_else(invalid)
+        };
+        let a = if digits.len() == 8 { channel(3)? } else { 255 };
+        Ok(Some(Color32::from_rgba_premultiplied(
+            channel(0)?,
+            channel(1)?,
+            channel(2)?,
+            a,
+        )))
     }
 }
@@ -0,0 +1,209 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::toml_file,
+    defines::{
+        APP_DIR_NAME, HISTORY_MAX_ENTRIES, HISTORY_SAVE_INTERVAL, RESUME_END_MARGIN,
+        RESUME_MIN_DURATION, RESUME_MIN_POSITION,
+    },
+    resources::display::Rotation,
+    services::player::ColorAdjust,
+};
+
+/// 重新打开文件时, 是否从上次的位置继续播放
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ResumeMode {
+    /// 询问
+    #[default]
+    Ask,
+    /// 自动继续播放
+    Always,
+    /// 总是从头播放
+    Never,
+}
+
+/// 只对一个文件生效的设置, 与观看记录一起保存
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct FileSettings {
+    /// 手动指定的画面旋转, None 时使用视频中记录的角度
+    pub rotation: Option<Rotation>,
+    /// 色彩调节
+    pub color: ColorAdjust,
+    /// 音频延迟(秒), 正数表示声音比画面晚
+    pub audio_delay: f64,
+}
+
+impl FileSettings {
+    fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// 一个文件的观看记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchEntry {
+    /// 上次播放到的位置(秒), 不需要继续播放时为 None
+    pub position: Option<f64>,
+    /// 文件时长(秒)
+    #[serde(default)]
+    pub duration: f64,
+    /// 上次播放的时间(unix 时间戳, 秒)
+    pub last_played: u64,
+    #[serde(default, skip_serializing_if = "FileSettings::is_default")]
+    pub settings: FileSettings,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HistoryFile {
+    /// key 是文件路径的 hash
+    entries: HashMap<String, WatchEntry>,
+}
+
+/// 观看记录, 保存在 $XDG_DATA_HOME/small-player/history.toml
+pub struct WatchHistory {
+    file: HistoryFile,
+    path: Option<PathBuf>,
+    dirty: bool,
+    last_save: Instant,
+}
+
+impl Default for WatchHistory {
+    fn default() -> Self {
+        let path = dirs::data_dir().map(|dir| dir.join(APP_DIR_NAME).join("history.toml"));
+        let file = path
+            .as_deref()
+            .and_then(toml_file::load)
+            .unwrap_or_default();
+        Self {
+            file,
+            path,
+            dirty: false,
+            last_save: Instant::now(),
+        }
+    }
+}
+
+impl WatchHistory {
+    /// 可以继续播放的位置
+    pub fn resume_position(&self, path: &str) -> Option<f64> {
+        self.file
+            .entries
+            .get(&path_hash(path))
+            .and_then(|entry| entry.position)
+    }
+
+    /// 更新播放位置, 时长太短 或者 快要播放完毕 的文件不记录
+    pub fn update(&mut self, path: &str, position: f64, duration: Option<f64>) {
+        let key = path_hash(path);
+        let duration = duration.unwrap_or_default();
+        let resume = duration >= RESUME_MIN_DURATION
+            && position >= RESUME_MIN_POSITION
+            && position <= duration - RESUME_END_MARGIN;
+
+        if !resume {
+            // 有单独设置的文件, 只清除播放位置
+            let remove = match self.file.entries.get_mut(&key) {
+                Some(entry) if entry.settings.is_default() => true,
+                Some(entry) if entry.position.is_some() => {
+                    entry.position = None;
+                    self.dirty = true;
+                    false
+                }
+                _ => false,
+            };
+            if remove {
+                self.file.entries.remove(&key);
+                self.dirty = true;
+            }
+            return;
+        }
+
+        let entry = self.entry_mut(path);
+        entry.position = Some(position);
+        entry.duration = duration;
+        self.dirty = true;
+    }
+
+    /// 文件的单独设置, 没有时使用默认值
+    pub fn file_settings(&self, path: &str) -> FileSettings {
+        self.file
+            .entries
+            .get(&path_hash(path))
+            .map(|entry| entry.settings.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_file_settings(&mut self, path: &str, settings: FileSettings) {
+        let entry = self.entry_mut(path);
+        entry.settings = settings;
+        if entry.settings.is_default() && entry.position.is_none() {
+            self.file.entries.remove(&path_hash(path));
+        }
+        self.dirty = true;
+    }
+
+    /// 文件的记录, 没有时新建一个, 并更新播放时间
+    fn entry_mut(&mut self, path: &str) -> &mut WatchEntry {
+        let last_played = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let entry = self
+            .file
+            .entries
+            .entry(path_hash(path))
+            .or_insert_with(|| WatchEntry {
+                position: None,
+                duration: 0.0,
+                last_played,
+                settings: FileSettings::default(),
+            });
+        entry.last_played = last_played;
+        entry
+    }
+
+    /// 距离上次保存超过一定时间后, 保存到文件
+    pub fn save_if_needed(&mut self) {
+        if self.last_save.elapsed() >= Duration::from_secs(HISTORY_SAVE_INTERVAL) {
+            self.save();
+        }
+    }
+
+    pub fn save(&mut self) {
+        self.last_save = Instant::now();
+        if !self.dirty {
+            return;
+        }
+        self.dirty = false;
+
+        // 只保留最近播放的记录
+        if self.file.entries.len() > HISTORY_MAX_ENTRIES {
+            let mut entries: Vec<_> = self.file.entries.drain().collect();
+            entries.sort_by_key(|(_, entry)| Reverse(entry.last_played));
+            entries.truncate(HISTORY_MAX_ENTRIES);
+            self.file.entries = entries.into_iter().collect();
+        }
+
+        if let Some(path) = &self.path {
+            toml_file::save(path, &self.file);
+        }
+    }
+}
+
+/// 文件路径的 hash (FNV-1a 64), 不直接保存路径
+fn path_hash(path: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in path.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -1 +1,3 @@
+pub mod history;
 pub mod player;
+pub mod settings;
@@ -1,17 +1,27 @@
-use std::ffi::CString;
-use std::time::Duration;
+use std::collections::VecDeque;
+use std::ffi::{CStr, CString};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::vec::IntoIter;
 
 use cpal::traits::HostTrait;
 use cpal::SupportedStreamConfig;
 use crossbeam_channel::Receiver;
+use parking_lot::Mutex;
 use rodio::{DeviceTrait, OutputStream, Sample, Sink, Source};
-use rsmpeg::avfilter::AVFilterGraph;
-
-use crate::defines::PLAY_MIN_INTERVAL;
+use rsmpeg::avfilter::{AVFilterContextMut, AVFilterGraph};
+use rsmpeg::avutil::AVFrame;
+use rsmpeg::error::RsmpegError;
+use rsmpeg::ffi;
+
+use crate::defines::{
+    AUDIO_DEVICE_CHECK_INTERVAL, AUDIO_TAP_SIZE, PLAY_MIN_INTERVAL, VOLUME_MAX, VOLUME_RAMP_TIME,
+};
 use crate::error::{PlayerError, Result};
 use crate::services::player::stream::{decode_frame, DecodeContext};
-use crate::services::player::PlayControl;
+use crate::services::player::{Equalizer, PlayControl};
 
 use super::PlayFrame;
 
@@ -51,7 +61,10 @@ pub fn audio_play_thread(
         }
 
         if let Ok(frame) = audio_frame_queue.try_recv() {
-            play_ctrl.play_audio(frame)?;
+            // 跳转之前解码的帧, 直接丢弃
+            if frame.serial == play_ctrl.serial() {
+                play_ctrl.play_audio(frame)?;
+            }
             empty_count = 0;
             continue;
         }
@@ -67,73 +80,286 @@ pub fn audio_play_thread(
     Ok(())
 }
 
-pub fn fetch_audio_source(
-    decode_ctx: &mut DecodeContext,
-    play_ctrl: &PlayControl,
-    audio_graph: &mut Option<AVFilterGraph>,
-) -> Result<Option<AudioFrame>> {
-    let frame = match decode_frame(play_ctrl, decode_ctx) {
-        Ok(None) => {
-            return Ok(None);
+/// 定期检查音频设备, 当前设备断开时 切换到默认设备
+///
+/// 枚举设备比较慢, 放在单独的线程中, 不阻塞解封装和播放
+pub fn audio_device_thread(play_ctrl: PlayControl) {
+    // 启动时先检查一次, 右键菜单 使用这里获取的设备列表
+    play_ctrl.check_audio_device();
+    let mut last_check = Instant::now();
+    while !play_ctrl.abort_request() && !play_ctrl.audio_finished() {
+        if last_check.elapsed() >= AUDIO_DEVICE_CHECK_INTERVAL {
+            play_ctrl.check_audio_device();
+            last_check = Instant::now();
         }
-        Ok(Some(frame)) => frame,
-        Err(e) => {
-            return Err(PlayerError::Error(e.to_string()));
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    log::info!("音频设备检查线程退出");
+}
+
+/// 音频滤镜图, 播放速度, 响度均衡, 声道混合, 音频设备的采样率 / 声道数改变 或 跳转后需要重新创建
+///
+/// 均衡器只在 使用 / 不使用 之间切换时需要重新创建, 增益通过滤镜命令修改
+pub struct AudioGraph {
+    graph: AVFilterGraph,
+    /// 创建时的播放速度
+    speed: f64,
+    /// 响度均衡的滤镜
+    loudness: Option<String>,
+    equalizer: Equalizer,
+    /// 声道混合的滤镜
+    downmix: Option<String>,
+    /// 输出的采样率
+    sample_rate: u32,
+    /// 输出的声道数
+    channels: u16,
+    /// 创建时的跳转序号
+    serial: usize,
+    /// 创建后送入的第一帧的显示时间(秒), 变速后的显示时间从这里开始计算
+    start_pts: Option<f64>,
+    /// 解码结束后 已经通知滤镜图, 只需要取出剩余的帧
+    eof: bool,
+    /// 重新创建时 从旧滤镜图中取出的帧, 先于新滤镜图的帧播放
+    drained: VecDeque<AudioFrame>,
+}
+
+impl AudioGraph {
+    /// 获取滤镜图中的滤镜
+    fn get_filter(&self, name: &CStr) -> Result<AVFilterContextMut> {
+        self.graph
+            .get_filter(name)
+            .ok_or_else(|| PlayerError::Error(format!("get {:?} failed", name)))
+    }
+
+    /// 结束输入, 取出滤镜中缓存的所有帧
+    fn flush(&mut self) -> Result<VecDeque<AudioFrame>> {
+        let mut frames = std::mem::take(&mut self.drained);
+        if self.eof {
+            return Ok(frames);
         }
-    };
+        self.eof = true;
+        self.get_filter(cstr::cstr!("abuffer@audio0"))?
+            .buffersrc_add_frame(None, None)
+            .map_err(|e| PlayerError::Error(e.to_string()))?;
+        let mut sink = self.get_filter(cstr::cstr!("abuffersink@out"))?;
+        loop {
+            match sink.buffersink_get_frame(None) {
+                Ok(frame) => frames.push_back(self.audio_frame(&frame)),
+                Err(RsmpegError::BufferSinkDrainError | RsmpegError::BufferSinkEofError) => break,
+                Err(e) => return Err(PlayerError::Error(e.to_string())),
+            }
+        }
+        Ok(frames)
+    }
 
-    if audio_graph.is_none() {
-        let default_config = play_ctrl.audio_default_config();
-        *audio_graph = Some(
-            audio_graph_parse(
-                frame.sample_rate,
-                frame.format,
-                frame.channel_layout,
-                frame.channels,
-                default_config.sample_rate().0,
+    /// 滤镜输出的帧 转换为播放的音频帧
+    fn audio_frame(&self, frame: &AVFrame) -> AudioFrame {
+        let start_pts = self.start_pts.unwrap_or_default();
+
+        // 音频的时间基 就是一个采样的时间, 即 采样率的倒数
+        // pts = frame.pts * 时间基 = frame.pts / frame.sample_rate
+        // 变速后 pts 按实际播放的时间增长, 需要换算回视频中的时间
+        let pts = frame.pts as f64 / frame.sample_rate as f64;
+        let pts = start_pts + (pts - start_pts) * self.speed;
+        let duration = frame.nb_samples as f64 / frame.sample_rate as f64;
+
+        let samples = unsafe {
+            std::slice::from_raw_parts(
+                frame.data[0] as *const f32,
+                (frame.nb_samples * frame.channels) as usize,
             )
-            .expect("Error while audio_graph_parse"),
-        );
-    }
+        };
 
-    let graph = audio_graph.as_mut().unwrap();
+        AudioFrame::new(
+            samples.to_vec(),
+            frame.channels as u16,
+            frame.sample_rate as u32,
+            pts,
+            duration,
+            self.serial,
+        )
+    }
 
-    graph
-        .get_filter(cstr::cstr!("abuffer@audio0"))
-        .expect("get abuffer@audio0 failed")
-        .buffersrc_add_frame(Some(frame), None)
-        .expect("Error while feeding the filtergraph");
+    /// 通过滤镜命令修改均衡器的增益
+    fn set_equalizer(&mut self, equalizer: Equalizer) {
+        if self.equalizer == equalizer {
+            return;
+        }
+        self.equalizer = equalizer;
+        if equalizer.is_flat() {
+            return;
+        }
+        for (target, cmd, arg) in equalizer.commands() {
+            let c_target = CString::new(target.as_str()).unwrap();
+            let c_cmd = CString::new(cmd).unwrap();
+            let c_arg = CString::new(arg.to_string()).unwrap();
+            let ret = unsafe {
+                ffi::avfilter_graph_send_command(
+                    self.graph.as_mut_ptr(),
+                    c_target.as_ptr(),
+                    c_cmd.as_ptr(),
+                    c_arg.as_ptr(),
+                    ptr::null_mut(),
+                    0,
+                    0,
+                )
+            };
+            if ret < 0 {
+                log::error!("滤镜命令 {} {}={} 失败, E: {}", target, cmd, arg, ret);
+            }
+        }
+    }
+}
 
-    let ctx = &mut graph
-        .get_filter(cstr::cstr!("abuffersink@out"))
-        .expect("get abuffersink@out failed");
+pub fn fetch_audio_source(
+    decode_ctx: &mut DecodeContext,
+    play_ctrl: &PlayControl,
+    audio_graph: &mut Option<AudioGraph>,
+) -> Result<Option<AudioFrame>> {
+    loop {
+        // 滤镜可能缓存了多帧 (如 慢速播放, 响度均衡的前瞻), 先取出已有的帧, 跳转之前的帧不再需要
+        let serial = decode_ctx.serial();
+        if let Some(graph) = audio_graph.as_mut().filter(|graph| graph.serial == serial) {
+            // 重新创建前 旧滤镜图中取出的帧
+            if let Some(source) = graph.drained.pop_front() {
+                return Ok(Some(source));
+            }
+            match graph
+                .get_filter(cstr::cstr!("abuffersink@out"))?
+                .buffersink_get_frame(None)
+            {
+                Ok(frame) => return Ok(Some(graph.audio_frame(&frame))),
+                Err(RsmpegError::BufferSinkDrainError | RsmpegError::BufferSinkEofError)
+                    if graph.eof =>
+                {
+                    return Ok(None);
+                }
+                Err(RsmpegError::BufferSinkDrainError | RsmpegError::BufferSinkEofError) => {}
+                Err(e) => return Err(PlayerError::Error(e.to_string())),
+            }
+        }
 
-    let frame = ctx
-        .buffersink_get_frame(None)
-        .expect("Get frame from buffer sink failed");
+        let frame = match decode_frame(play_ctrl, decode_ctx) {
+            Ok(None) => {
+                // 解码结束时 滤镜中还缓存着声音 (如 响度均衡的前瞻), 通知滤镜图结束后取出
+                let serial = decode_ctx.serial();
+                match audio_graph.as_mut().filter(|graph| graph.serial == serial) {
+                    Some(graph) if !graph.eof && !play_ctrl.abort_request() => {
+                        graph.eof = true;
+                        graph
+                            .get_filter(cstr::cstr!("abuffer@audio0"))?
+                            .buffersrc_add_frame(None, None)
+                            .map_err(|e| PlayerError::Error(e.to_string()))?;
+                        continue;
+                    }
+                    _ => return Ok(None),
+                }
+            }
+            Ok(Some(frame)) => frame,
+            Err(e) => {
+                return Err(PlayerError::Error(e.to_string()));
+            }
+        };
+        // 解码时可能遇到跳转后的第一个 packet, 序号要在解码之后读取
+        let serial = decode_ctx.serial();
+
+        let speed = play_ctrl.speed();
+        let config = play_ctrl.audio_default_config();
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels();
+        let equalizer = play_ctrl.equalizer();
+        let loudness = play_ctrl.loudness_filter();
+        let downmix = play_ctrl.downmix_filter();
+        let rebuild = match audio_graph {
+            Some(graph) => {
+                graph.speed != speed
+                    || graph.serial != serial
+                    || graph.sample_rate != sample_rate
+                    || graph.channels != channels
+                    || graph.loudness != loudness
+                    || graph.downmix != downmix
+                    || graph.equalizer.is_flat() != equalizer.is_flat()
+            }
+            None => true,
+        };
+        if rebuild {
+            // 取出旧滤镜图中缓存的帧, 跳转前的帧直接丢弃
+            let drained = match audio_graph.take() {
+                Some(mut old) if old.serial == serial => old.flush()?,
+                _ => VecDeque::new(),
+            };
+            let parse = |filter: String| {
+                audio_graph_parse(
+                    frame.sample_rate,
+                    frame.format,
+                    frame.channel_layout,
+                    frame.channels,
+                    sample_rate,
+                    channels,
+                    &filter,
+                )
+            };
+            // 声道混合的滤镜可能不可用 (如 FFmpeg 编译时没有 libmysofa), 失败时不使用声道混合,
+            // 仍然失败时 只保留变速, 都失败时 结束音频解码
+            let graph = parse(audio_filter(
+                speed,
+                loudness.as_deref(),
+                &equalizer,
+                downmix.as_deref(),
+            ))
+            .or_else(|e| {
+                log::error!("创建音频滤镜图失败, 不使用声道混合重试, E: {}", e);
+                parse(audio_filter(speed, loudness.as_deref(), &equalizer, None))
+            })
+            .or_else(|e| {
+                log::error!("创建音频滤镜图失败, 只使用变速重试, E: {}", e);
+                parse(audio_filter(speed, None, &Equalizer::default(), None))
+            })?;
+            *audio_graph = Some(AudioGraph {
+                graph,
+                speed,
+                loudness,
+                equalizer,
+                downmix,
+                sample_rate,
+                channels,
+                serial,
+                start_pts: None,
+                eof: false,
+                drained,
+            });
+        }
 
-    // 音频的时间基 就是一个采样的时间, 即 采样率的倒数
-    // pts = frame.pts * 时间基 = frame.pts / frame.sample_rate
-    let pts = frame.pts as f64 / frame.sample_rate as f64;
-    let duration = frame.nb_samples as f64 / frame.sample_rate as f64;
+        let audio_graph = audio_graph.as_mut().unwrap();
+        audio_graph.set_equalizer(equalizer);
+        audio_graph
+            .start_pts
+            .get_or_insert(frame.pts as f64 / frame.sample_rate as f64);
+        audio_graph
+            .get_filter(cstr::cstr!("abuffer@audio0"))?
+            .buffersrc_add_frame(Some(frame), None)
+            .map_err(|e| PlayerError::Error(e.to_string()))?;
+    }
+}
 
-    let volume = play_ctrl.volume();
-    let samples = unsafe {
-        std::slice::from_raw_parts(
-            frame.data[0] as *const f32,
-            (frame.nb_samples * frame.channels) as usize,
-        )
-    };
-    let samples: Vec<f32> = samples.iter().map(|s| s * volume).collect();
-
-    let source = AudioFrame::new(
-        samples,
-        frame.channels as u16,
-        frame.sample_rate as u32,
-        pts,
-        duration,
-    );
-    Ok(Some(source))
+/// 变速, 响度均衡, 均衡器, 声道混合 依次连接的滤镜, 没有滤镜时为空
+fn audio_filter(
+    speed: f64,
+    loudness: Option<&str>,
+    equalizer: &Equalizer,
+    downmix: Option<&str>,
+) -> String {
+    let mut filters = vec![];
+    // 变速不变调
+    if speed != 1.0 {
+        filters.push(format!("atempo={}", speed));
+    }
+    filters.extend(loudness.map(|filter| filter.to_string()));
+    if !equalizer.is_flat() {
+        filters.push(equalizer.filter());
+    }
+    filters.extend(downmix.map(|filter| filter.to_string()));
+    filters.join(",")
 }
 
 // AudioDevice::SAMPLE_RATE.0
@@ -143,6 +369,8 @@ pub fn audio_graph_parse(
     src_channel_layout: u64,
     src_channels: i32,
     dst_sample_rate: u32,
+    dst_channels: u16,
+    filter: &str,
 ) -> Result<AVFilterGraph> {
     // 上下两部分, 上面是高清原始屏, 下面是低分辨率的 机械屏/龙鳞屏
     // 一个 视频源文件的帧 和 发送到screen上的 RGB帧, 合并
@@ -156,9 +384,21 @@ pub fn audio_graph_parse(
         src_sample_rate, src_format, src_channels, src_channel_layout
     );
 
+    let filter = if filter.is_empty() {
+        String::new()
+    } else {
+        format!("{},", filter)
+    };
+
+    // 输出设备的声道布局, 声道不同时由 FFmpeg 按标准系数混合, 再按设备的声道顺序重新排列
+    let (layout, remap) = output_layout(dst_channels);
+    let remap = match remap {
+        Some(remap) => format!(",{},aformat=sample_fmts=flt", remap),
+        None => String::new(),
+    };
     let format_filter = format!(
-        "[audio0_src] aformat=sample_rates={}:sample_fmts=flt:channel_layouts=stereo [audio0_out]",
-        dst_sample_rate
+        "[audio0_src] {}aformat=sample_rates={}:sample_fmts=flt:channel_layouts={}{} [audio0_out]",
+        filter, dst_sample_rate, layout, remap
     );
 
     let buffersink_filter = "[audio0_out] abuffersink@out";
@@ -178,6 +418,27 @@ pub fn audio_graph_parse(
     Ok(filter_graph)
 }
 
+/// 输出设备的声道数 对应的 FFmpeg 声道布局, 和 把 FFmpeg 的声道顺序重新排列为设备顺序的滤镜
+///
+/// 没有对应布局的声道数 输出立体声, 由播放时的声道转换补齐
+fn output_layout(channels: u16) -> (&'static str, Option<&'static str>) {
+    // ALSA 的声道顺序是 FL FR RL RR FC LFE (SL SR), FFmpeg 是 FL FR FC LFE BL BR (SL SR)
+    // WASAPI 和 CoreAudio 与 FFmpeg 相同, 不需要重新排列
+    let alsa = cfg!(target_os = "linux");
+    match channels {
+        1 => ("mono", None),
+        4 => ("quad", None),
+        6 if alsa => ("5.1", Some("pan=5.1|c0=FL|c1=FR|c2=BL|c3=BR|c4=FC|c5=LFE")),
+        6 => ("5.1", None),
+        8 if alsa => (
+            "7.1",
+            Some("pan=7.1|c0=FL|c1=FR|c2=BL|c3=BR|c4=FC|c5=LFE|c6=SL|c7=SR"),
+        ),
+        8 => ("7.1", None),
+        _ => ("stereo", None),
+    }
+}
+
 #[derive(Clone)]
 pub struct AudioFrame {
     pub samples: IntoIter<f32>,
@@ -185,6 +446,8 @@ pub struct AudioFrame {
     pub sample_rate: u32,
     pub pts: f64,
     pub duration: f64,
+    /// 跳转序号
+    pub serial: usize,
 }
 
 impl AudioFrame {
@@ -194,6 +457,7 @@ impl AudioFrame {
         sample_rate: u32,
         pts: f64,
         duration: f64,
+        serial: usize,
     ) -> Self {
         Self {
             samples: samples.into_iter(),
@@ -201,6 +465,7 @@ impl AudioFrame {
             sample_rate,
             pts,
             duration,
+            serial,
         }
     }
 }
@@ -258,17 +523,222 @@ impl Source for AudioFrame {
     }
 }
 
+/// 最近送入音频设备的采样, 混合成单声道, 供界面显示频谱和波形
+#[derive(Default)]
+pub struct AudioTap {
+    samples: Mutex<VecDeque<f32>>,
+    sample_rate: AtomicU32,
+}
+
+impl AudioTap {
+    pub fn push(&self, frame: &AudioFrame) {
+        let channels = frame.channels.max(1) as usize;
+        let mut samples = self.samples.lock();
+        samples.extend(
+            frame
+                .samples
+                .as_slice()
+                .chunks(channels)
+                .map(|c| c.iter().sum::<f32>() / channels as f32),
+        );
+        let len = samples.len();
+        if len > AUDIO_TAP_SIZE {
+            samples.drain(..len - AUDIO_TAP_SIZE);
+        }
+        self.sample_rate.store(frame.sample_rate, Ordering::Relaxed);
+    }
+
+    /// 最近的 AUDIO_TAP_SIZE 个采样(不足时前面补 0) 和 采样率
+    pub fn samples(&self) -> (Vec<f32>, u32) {
+        let samples = self.samples.lock();
+        let mut data = vec![0.0; AUDIO_TAP_SIZE - samples.len()];
+        data.extend(samples.iter());
+        (data, self.sample_rate.load(Ordering::Relaxed))
+    }
+}
+
+/// 输出阶段的音量和静音状态, 修改后对已经送入设备的声音也立即生效
+pub struct OutputGain {
+    /// 音量, 0.0 ~ VOLUME_MAX
+    volume: AtomicU32,
+    mute: AtomicBool,
+    /// 正在使用的增益, 播放时逐采样向目标增益过渡
+    current: AtomicU32,
+}
+
+impl OutputGain {
+    pub fn new(volume: f32, mute: bool) -> Self {
+        let gain = Self {
+            volume: AtomicU32::new(0),
+            mute: AtomicBool::new(false),
+            current: AtomicU32::new(0),
+        };
+        gain.reset(volume, mute);
+        gain
+    }
+
+    /// 直接设置音量和静音, 不过渡, 用于开始播放之前
+    pub fn reset(&self, volume: f32, mute: bool) {
+        self.set_volume(volume);
+        self.set_mute(mute);
+        self.current
+            .store(self.target().to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn set_volume(&self, volume: f32) {
+        let volume = volume.clamp(0.0, VOLUME_MAX);
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    pub fn set_mute(&self, mute: bool) {
+        self.mute.store(mute, Ordering::Relaxed);
+    }
+
+    pub fn mute(&self) -> bool {
+        self.mute.load(Ordering::Relaxed)
+    }
+
+    /// 目标增益, 静音时为 0
+    fn target(&self) -> f32 {
+        if self.mute() {
+            0.0
+        } else {
+            self.volume()
+        }
+    }
+
+    /// 向目标增益前进一个采样帧, 返回这一帧使用的增益
+    fn next(&self, sample_rate: u32) -> f32 {
+        let target = self.target();
+        let current = f32::from_bits(self.current.load(Ordering::Relaxed));
+        if current == target {
+            return current;
+        }
+        let step = 1.0 / (VOLUME_RAMP_TIME * sample_rate.max(1) as f32);
+        let next = if current < target {
+            (current + step).min(target)
+        } else {
+            (current - step).max(target)
+        };
+        self.current.store(next.to_bits(), Ordering::Relaxed);
+        next
+    }
+}
+
+/// 在输出阶段调节音量的音源, 同一采样帧的各个声道使用相同的增益
+pub struct GainSource<S> {
+    source: S,
+    gain: Arc<OutputGain>,
+    /// 下一个采样所在的声道
+    channel: u16,
+    /// 当前采样帧的增益
+    current: f32,
+}
+
+impl<S> GainSource<S> {
+    pub fn new(source: S, gain: Arc<OutputGain>) -> Self {
+        Self {
+            source,
+            gain,
+            channel: 0,
+            current: 0.0,
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Iterator for GainSource<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+        if self.channel == 0 {
+            self.current = self.gain.next(self.source.sample_rate());
+        }
+        self.channel = (self.channel + 1) % self.source.channels().max(1);
+
+        let sample = sample * self.current;
+        if self.current > 1.0 {
+            Some(soft_limit(sample))
+        } else {
+            Some(sample)
+        }
+    }
+}
+
+impl<S: Source<Item = f32>> Source for GainSource<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        self.source.total_duration()
+    }
+}
+
+/// 软限幅, 超过阈值的部分平滑地压缩到 1.0 以内, 放大音量时避免削波失真
+fn soft_limit(sample: f32) -> f32 {
+    const THRESHOLD: f32 = 0.8;
+    let abs = sample.abs();
+    if abs <= THRESHOLD {
+        return sample;
+    }
+    let over = (abs - THRESHOLD) / (1.0 - THRESHOLD);
+    (THRESHOLD + (1.0 - THRESHOLD) * over.tanh()).copysign(sample)
+}
+
+/// 所有音频输出设备的名字, 获取失败时返回 None
+pub fn output_devices() -> Option<Vec<String>> {
+    match cpal::default_host().output_devices() {
+        Ok(devices) => Some(devices.filter_map(|d| d.name().ok()).collect()),
+        Err(e) => {
+            log::error!("获取音频设备失败, E: {}", e);
+            None
+        }
+    }
+}
+
 pub struct AudioDevice {
     _stream: OutputStream,
     sink: Sink,
     default_config: SupportedStreamConfig,
+    /// 设备的名字
+    name: String,
 }
 
 impl AudioDevice {
-    pub fn new() -> Result<Self> {
-        let default_device = cpal::default_host()
-            .default_output_device()
-            .ok_or(PlayerError::NoAudioDevice)?;
+    /// 打开名字为 name 的设备, 设备不存在 或 name 为 None 时使用默认设备
+    pub fn new(name: Option<&str>) -> Result<Self> {
+        let host = cpal::default_host();
+        let device = name.and_then(|name| {
+            let device = host
+                .output_devices()
+                .ok()?
+                .find(|d| d.name().map(|n| n == name).unwrap_or(false));
+            if device.is_none() {
+                log::warn!("音频设备 {} 不存在, 使用默认设备", name);
+            }
+            device
+        });
+        let default_device = match device {
+            Some(device) => device,
+            None => host
+                .default_output_device()
+                .ok_or(PlayerError::NoAudioDevice)?,
+        };
+        let name = default_device.name().unwrap_or_default();
+        log::info!("音频设备: {}", name);
 
         let default_config = default_device
             .default_output_config()
@@ -294,6 +764,7 @@ impl AudioDevice {
             _stream,
             sink,
             default_config,
+            name,
         })
     }
 
@@ -301,6 +772,10 @@ impl AudioDevice {
         self.default_config.clone()
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn play_source<S>(&self, audio_source: S)
     where
         S: Source + Send + 'static,
@@ -310,14 +785,6 @@ impl AudioDevice {
         self.sink.append(audio_source);
     }
 
-    pub fn set_mute(&self, mute: bool) {
-        if mute {
-            self.sink.set_volume(0.0);
-        } else {
-            self.sink.set_volume(1.0);
-        }
-    }
-
     pub fn set_pause(&self, pause: bool) {
         if pause {
             self.sink.pause();
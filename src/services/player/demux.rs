@@ -1,53 +1,122 @@
 use std::sync::atomic::AtomicBool;
 use std::time::Duration;
-use std::{ffi::CString, sync::Arc};
+use std::{
+    ffi::{CStr, CString},
+    ptr,
+    sync::Arc,
+};
 
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use parking_lot::{Mutex, RwLock};
 use rsmpeg::ffi::AVRational;
 use rsmpeg::{
-    avcodec::{AVCodecContext, AVPacket},
-    avformat::AVFormatContextInput,
+    avcodec::{AVCodec, AVCodecContext, AVPacket},
+    avformat::{AVFormatContextInput, AVStreamRef},
     ffi,
 };
 
 use crate::error::{PlayerError, Result};
-use crate::services::player::{Command, StreamType};
+use crate::services::player::{AudioTrack, Command, PlayOptions, ReplayGain, StreamType};
 
 use super::{
     audio::{AudioDevice, AudioFrame},
     stream::DecodeContext,
-    video::VideoFrame,
+    video::{redraw_paused_frame, VideoFrame},
     PacketQueue, PlayControl, PlayState,
 };
 
 pub fn demux_thread(mut demux_ctx: DemuxContext, cmd_rx: Receiver<Command>) {
     let (video_stream_idx, audio_stream_idx) = demux_ctx.stream_idx();
-    loop {
-        match cmd_rx.try_recv() {
-            Ok(Command::Terminate) => {
-                log::info!("run abort_request cmd");
-                demux_ctx.ctrl.set_abort_request(true);
-                break;
-            }
-            Ok(Command::Pause(pause)) => {
-                log::info!("run pause cmd: {pause}");
-                demux_ctx.ctrl.set_pause(pause);
-            }
-            Ok(Command::Mute(mute)) => {
-                log::info!("recv mute command: {mute}");
-                demux_ctx.ctrl.set_mute(mute);
-            }
-            Ok(Command::Volume(volume)) => {
-                log::info!("recv volume command: {volume}");
-                demux_ctx.ctrl.set_volume(volume);
-            }
-            Err(TryRecvError::Disconnected) => {
-                demux_ctx.ctrl.set_abort_request(true);
-                log::info!("demux_thread disconnected");
-                break;
+    'demux: loop {
+        // 一次处理完所有命令, 拖动滑块时 ui 每一帧都会发送命令
+        let mut redraw = false;
+        loop {
+            match cmd_rx.try_recv() {
+                Ok(Command::Terminate) => {
+                    log::info!("run abort_request cmd");
+                    demux_ctx.ctrl.set_abort_request(true);
+                    break 'demux;
+                }
+                Ok(Command::Pause(pause)) => {
+                    log::info!("run pause cmd: {pause}");
+                    demux_ctx.ctrl.set_pause(pause);
+                }
+                Ok(Command::Mute(mute)) => {
+                    log::info!("recv mute command: {mute}");
+                    demux_ctx.ctrl.set_mute(mute);
+                }
+                Ok(Command::Volume(volume)) => {
+                    log::info!("recv volume command: {volume}");
+                    demux_ctx.ctrl.set_volume(volume);
+                }
+                Ok(Command::Seek(position)) => {
+                    log::info!("recv seek command: {position}");
+                    demux_ctx.seek(position);
+                }
+                Ok(Command::Speed(speed)) => {
+                    log::info!("recv speed command: {speed}");
+                    demux_ctx.ctrl.set_speed(speed);
+                }
+                Ok(Command::VideoFilter(filter)) => {
+                    log::info!("recv video filter command: {filter}");
+                    demux_ctx.ctrl.set_video_filter(filter);
+                }
+                Ok(Command::Rotation(degrees)) => {
+                    log::info!("recv rotation command: {degrees}");
+                    demux_ctx.ctrl.set_rotation(degrees);
+                }
+                Ok(Command::Color(color)) => {
+                    demux_ctx.ctrl.set_color(color);
+                    redraw = true;
+                }
+                Ok(Command::ColorSpace(color_space)) => {
+                    log::info!("recv color space command: {:?}", color_space);
+                    demux_ctx.ctrl.set_color_space(color_space);
+                }
+                Ok(Command::ToneMapping(tone_mapping)) => {
+                    log::info!("recv tone mapping command: {:?}", tone_mapping);
+                    demux_ctx.ctrl.set_tone_mapping(tone_mapping);
+                }
+                Ok(Command::DisplaySize(width, height)) => {
+                    demux_ctx.ctrl.set_display_size(width, height);
+                }
+                Ok(Command::Scaler(scaler)) => {
+                    log::info!("recv scaler command: {:?}", scaler);
+                    demux_ctx.ctrl.set_scaler(scaler);
+                }
+                Ok(Command::Loudness(loudness)) => {
+                    log::info!("recv loudness command: {:?}", loudness);
+                    demux_ctx.ctrl.set_loudness(loudness);
+                }
+                Ok(Command::AudioDelay(delay)) => {
+                    log::info!("recv audio delay command: {delay}");
+                    demux_ctx.ctrl.set_audio_delay(delay);
+                }
+                Ok(Command::Downmix(downmix)) => {
+                    log::info!("recv downmix command: {:?}", downmix);
+                    demux_ctx.ctrl.set_downmix(downmix);
+                }
+                Ok(Command::Equalizer(equalizer)) => {
+                    demux_ctx.ctrl.set_equalizer(equalizer);
+                }
+                Ok(Command::AudioDevice(name)) => {
+                    log::info!("recv audio device command: {:?}", name);
+                    demux_ctx.ctrl.set_preferred_audio_device(name.clone());
+                    if let Err(e) = demux_ctx.ctrl.set_audio_device(name.as_deref()) {
+                        log::error!("切换音频设备失败, E: {}", e);
+                    }
+                }
+                Err(TryRecvError::Disconnected) => {
+                    demux_ctx.ctrl.set_abort_request(true);
+                    log::info!("demux_thread disconnected");
+                    break 'demux;
+                }
+                Err(TryRecvError::Empty) => break,
             }
-            Err(TryRecvError::Empty) => {}
+        }
+        // 暂停时 解码线程不再输出帧, 由这里用新的色彩调节重新生成当前画面
+        if redraw && demux_ctx.ctrl.pause() {
+            redraw_paused_frame(&demux_ctx.ctrl);
         }
 
         // 暂停 / 声音队列已满 / 视频队列已满
@@ -91,8 +160,10 @@ pub fn demux_thread(mut demux_ctx: DemuxContext, cmd_rx: Receiver<Command>) {
     log::info!("解封装线程退出");
 }
 
+/// 音轨的选择顺序: 指定的音轨, 符合语言偏好的音轨, 由 FFmpeg 选择的音轨
 pub fn demux_init(
     filename: String,
+    options: &PlayOptions,
 ) -> Result<(
     AVFormatContextInput,
     Option<(usize, AVCodecContext)>,
@@ -108,7 +179,7 @@ pub fn demux_init(
         ))
     })?;
 
-    // 获取视频解码器
+    // 获取视频解码器, 音频文件的封面图片不作为视频, 这样可以显示音频可视化
     let video_decoder = ifmt_ctx
         .find_best_stream(ffi::AVMediaType_AVMEDIA_TYPE_VIDEO)
         .map_err(|e| {
@@ -116,7 +187,13 @@ pub fn demux_init(
                 "find_best_stream video failed, E: {}",
                 e.to_string()
             ))
-        })?;
+        })?
+        .filter(
+            |(stream_idx, _)| match ifmt_ctx.streams().get(*stream_idx) {
+                Some(stream) => stream.disposition & ffi::AV_DISPOSITION_ATTACHED_PIC as i32 == 0,
+                None => false,
+            },
+        );
     let vdec = if let Some((stream_idx, decoder)) = video_decoder {
         let mut vdec_ctx = AVCodecContext::new(&decoder);
         let av_stream = ifmt_ctx.streams().get(stream_idx).ok_or_else(|| {
@@ -132,15 +209,26 @@ pub fn demux_init(
         None
     };
 
-    // 获取音频解码器
-    let audio_decoder = ifmt_ctx
-        .find_best_stream(ffi::AVMediaType_AVMEDIA_TYPE_AUDIO)
-        .map_err(|e| {
-            PlayerError::Error(format!(
-                "find_best_stream audio failed, E: {}",
-                e.to_string()
-            ))
-        })?;
+    // 获取音频解码器, 优先使用指定的 或 符合语言偏好的音轨
+    let preferred_audio = options
+        .audio_track
+        .filter(|idx| audio_tracks(&ifmt_ctx).iter().any(|t| t.stream_idx == *idx))
+        .or_else(|| preferred_audio_stream(&ifmt_ctx, &options.audio_languages))
+        .and_then(|idx| {
+            let codec_id = ifmt_ctx.streams().get(idx)?.codecpar().codec_id;
+            AVCodec::find_decoder(codec_id).map(|decoder| (idx, decoder))
+        });
+    let audio_decoder = match preferred_audio {
+        Some(decoder) => Some(decoder),
+        None => ifmt_ctx
+            .find_best_stream(ffi::AVMediaType_AVMEDIA_TYPE_AUDIO)
+            .map_err(|e| {
+                PlayerError::Error(format!(
+                    "find_best_stream audio failed, E: {}",
+                    e.to_string()
+                ))
+            })?,
+    };
     let adec = if let Some((stream_idx, decoder)) = audio_decoder {
         let mut adec_ctx = AVCodecContext::new(&decoder);
         {
@@ -158,17 +246,128 @@ pub fn demux_init(
     Ok((ifmt_ctx, vdec, adec))
 }
 
+/// 跳转到 position(秒) 之前最近的关键帧, 失败时返回 false
+pub fn seek_input(ifmt_ctx: &mut AVFormatContextInput, position: f64) -> bool {
+    let mut ts = (position * ffi::AV_TIME_BASE as f64) as i64;
+    if ifmt_ctx.start_time != ffi::AV_NOPTS_VALUE {
+        ts += ifmt_ctx.start_time;
+    }
+    let ret = unsafe {
+        ffi::av_seek_frame(
+            ifmt_ctx.as_mut_ptr(),
+            -1,
+            ts,
+            ffi::AVSEEK_FLAG_BACKWARD as i32,
+        )
+    };
+    if ret < 0 {
+        log::error!("跳转到 {}s 失败, E: {}", position, ret);
+    }
+    ret >= 0
+}
+
+/// 文件中所有的音轨
+pub fn audio_tracks(ifmt_ctx: &AVFormatContextInput) -> Vec<AudioTrack> {
+    let streams = ifmt_ctx.streams();
+    (0..streams.num())
+        .filter_map(|idx| {
+            let stream = streams.get(idx)?;
+            if stream.codecpar().codec_type != ffi::AVMediaType_AVMEDIA_TYPE_AUDIO {
+                return None;
+            }
+            Some(AudioTrack {
+                stream_idx: idx,
+                language: stream_metadata(&stream, cstr::cstr!("language"))
+                    .map(|lang| lang.to_lowercase()),
+                title: stream_metadata(&stream, cstr::cstr!("title")),
+            })
+        })
+        .collect()
+}
+
+/// 视频需要顺时针旋转的角度 [0, 360), 来自 display matrix 或 旧文件中的 rotate 标签
+pub fn stream_rotation(stream: &AVStreamRef) -> f64 {
+    let matrix = unsafe {
+        ffi::av_stream_get_side_data(
+            stream.as_ptr(),
+            ffi::AVPacketSideDataType_AV_PKT_DATA_DISPLAYMATRIX,
+            ptr::null_mut(),
+        )
+    };
+    // display matrix 中的角度是逆时针的
+    let degrees = if !matrix.is_null() {
+        -unsafe { ffi::av_display_rotation_get(matrix as *const i32) }.round()
+    } else {
+        stream_metadata(stream, cstr::cstr!("rotate"))
+            .and_then(|rotate| rotate.trim().parse().ok())
+            .unwrap_or(0.0)
+    };
+    if degrees.is_nan() {
+        return 0.0;
+    }
+    degrees.rem_euclid(360.0)
+}
+
+/// FFmpeg 从 REPLAYGAIN_* 标签中解析出的 ReplayGain 信息
+pub fn stream_replay_gain(stream: &AVStreamRef) -> Option<ReplayGain> {
+    let data = unsafe {
+        ffi::av_stream_get_side_data(
+            stream.as_ptr(),
+            ffi::AVPacketSideDataType_AV_PKT_DATA_REPLAYGAIN,
+            ptr::null_mut(),
+        )
+    };
+    if data.is_null() {
+        return None;
+    }
+    let rg = unsafe { *(data as *const ffi::AVReplayGain) };
+    // 增益未知时为 INT32_MIN, 峰值未知时为 0, 单位都是 1/100000
+    let gain = |gain: i32| (gain != i32::MIN).then(|| gain as f32 / 100000.0);
+    let peak = |peak: u32| (peak != 0).then(|| peak as f32 / 100000.0);
+    Some(ReplayGain {
+        track_gain: gain(rg.track_gain),
+        track_peak: peak(rg.track_peak),
+        album_gain: gain(rg.album_gain),
+        album_peak: peak(rg.album_peak),
+    })
+}
+
+/// 按照语言偏好的顺序, 找到第一个匹配的音轨
+fn preferred_audio_stream(ifmt_ctx: &AVFormatContextInput, languages: &[String]) -> Option<usize> {
+    if languages.is_empty() {
+        return None;
+    }
+    let tracks = audio_tracks(ifmt_ctx);
+    languages.iter().find_map(|lang| {
+        tracks
+            .iter()
+            .find(|track| track.language.as_ref() == Some(lang))
+            .map(|track| track.stream_idx)
+    })
+}
+
+/// 流的 metadata 中的标签, 例如 language 的值 "chi", "eng"
+fn stream_metadata(stream: &AVStreamRef, key: &CStr) -> Option<String> {
+    let entry = unsafe { ffi::av_dict_get(stream.metadata, key.as_ptr(), ptr::null(), 0) };
+    if entry.is_null() {
+        return None;
+    }
+    let value = unsafe { CStr::from_ptr((*entry).value) };
+    Some(value.to_string_lossy().into_owned())
+}
+
 pub struct DemuxContext {
     ctrl: PlayControl,
     ifmt_ctx: AVFormatContextInput,
     video_queue: Arc<Mutex<PacketQueue>>,
     audio_queue: Arc<Mutex<PacketQueue>>,
+    /// 每个 packet 队列最多占用的内存(字节)
+    packet_queue_mem: i32,
 }
 
 impl DemuxContext {
     /// 无效的流索引
     pub const UNKNOWN_STREAM_IDX: i32 = -1;
-    pub const MAX_MEM_SIZE: i32 = 16 * 1024 * 1024;
 
     pub fn new(
         ifmt_ctx: AVFormatContextInput,
@@ -176,22 +375,31 @@ impl DemuxContext {
         audio_frame_tx: Sender<AudioFrame>,
         video_frame_tx: Sender<VideoFrame>,
         abort_request: Arc<AtomicBool>,
+        packet_queue_mem: i32,
+        audio_device: Option<&str>,
     ) -> (Self, PlayControl) {
         let video_queue = Arc::new(Mutex::new(PacketQueue::new(
             Self::UNKNOWN_STREAM_IDX,
-            Self::MAX_MEM_SIZE,
+            packet_queue_mem,
         )));
         let audio_queue = Arc::new(Mutex::new(PacketQueue::new(
             Self::UNKNOWN_STREAM_IDX,
-            Self::MAX_MEM_SIZE,
+            packet_queue_mem,
         )));
 
-        // 获取音频设备
-        let audio_dev = AudioDevice::new()
+        // 获取音频设备, 指定的设备不存在时 使用默认设备
+        let audio_dev = AudioDevice::new(audio_device)
             .map_err(|e| {
                 state_tx.send(PlayState::Error(e)).ok();
             })
             .unwrap();
+        if let Some(name) = audio_device {
+            if audio_dev.name() != name {
+                state_tx
+                    .send(PlayState::AudioDeviceLost(name.to_string()))
+                    .ok();
+            }
+        }
         let audio_dev = Arc::new(RwLock::new(audio_dev));
 
         // 控制播放器的行为
@@ -202,6 +410,7 @@ impl DemuxContext {
             video_frame_tx,
             abort_request,
         );
+        ctrl.set_preferred_audio_device(audio_device.map(str::to_string));
         let ctrl0 = ctrl.clone();
 
         (
@@ -210,6 +419,7 @@ impl DemuxContext {
                 ctrl,
                 video_queue,
                 audio_queue,
+                packet_queue_mem,
             },
             ctrl0,
         )
@@ -225,6 +435,18 @@ impl DemuxContext {
         Ok(time_base)
     }
 
+    /// 跳转到 position(秒), 清空 packet 队列, 之后读取的 packet 使用新的跳转序号
+    pub fn seek(&mut self, position: f64) {
+        if !seek_input(&mut self.ifmt_ctx, position) {
+            return;
+        }
+        let serial = self.ctrl.next_serial();
+        self.video_queue.lock().flush(serial);
+        self.audio_queue.lock().flush(serial);
+        self.ctrl.reset_clocks(position);
+        self.ctrl.set_demux_finished(false);
+    }
+
     pub fn read_packet(
         &mut self,
     ) -> std::result::Result<Option<AVPacket>, rsmpeg::error::RsmpegError> {
@@ -239,8 +461,10 @@ impl DemuxContext {
         if let Some((stream_idx, dec_ctx)) = decode {
             let stream_idx = stream_idx as i32;
 
-            let packet_queue =
-                Arc::new(Mutex::new(PacketQueue::new(stream_idx, Self::MAX_MEM_SIZE)));
+            let packet_queue = Arc::new(Mutex::new(PacketQueue::new(
+                stream_idx,
+                self.packet_queue_mem,
+            )));
 
             let decode_ctx = DecodeContext::new(dec_ctx, packet_queue.clone());
 
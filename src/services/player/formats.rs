@@ -0,0 +1,112 @@
+use std::ffi::CStr;
+use std::os::raw::c_void;
+
+use rsmpeg::ffi;
+
+/// 只包含音频的格式
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "aac", "ac3", "aif", "aifc", "aiff", "amr", "ape", "au", "caf", "dts", "eac3", "flac", "gsm",
+    "m4a", "mka", "mp2", "mp3", "mpc", "oga", "ogg", "opus", "ra", "spx", "tak", "tta", "voc",
+    "w64", "wav", "wma", "wv",
+];
+
+/// 播放列表格式
+const PLAYLIST_EXTENSIONS: &[&str] = &["ffconcat", "m3u", "m3u8", "pls"];
+
+/// 字幕格式
+const SUBTITLE_EXTENSIONS: &[&str] = &[
+    "ass", "idx", "jss", "lrc", "mpl2", "pjs", "rt", "sami", "smi", "srt", "ssa", "stl", "sub",
+    "sup", "vtt",
+];
+
+/// 不是音视频的 demuxer: 图片序列, 文本(tty), 原始数据(bin)
+/// 它们的扩展名(jpg, png, txt, nfo 等) 不能当作音视频文件添加到播放列表
+const SKIPPED_DEMUXERS: &[&str] = &["image2", "image2pipe", "tty", "bin"];
+
+/// 没有声明扩展名的容器 demuxer (通过探测识别), 使用这里的扩展名
+const FALLBACK_EXTENSIONS: &[(&str, &[&str])] = &[
+    ("mpegts", &["ts", "m2ts", "mts", "m2t", "tp", "trp"]),
+    ("mpeg", &["mpg", "mpeg", "vob", "m2p", "ps"]),
+    ("asf", &["asf", "wmv", "wma"]),
+];
+
+/// 链接的 FFmpeg 可以解封装的文件扩展名, 按类型分组
+#[derive(Debug, Default, Clone)]
+pub struct MediaFormats {
+    pub video: Vec<String>,
+    pub audio: Vec<String>,
+    pub playlist: Vec<String>,
+    pub subtitle: Vec<String>,
+}
+
+impl MediaFormats {
+    /// 遍历 FFmpeg 中编译进来的所有 demuxer, 收集它们声明的扩展名
+    pub fn from_demuxers() -> Self {
+        let mut formats = Self::default();
+
+        for ext in demuxer_extensions() {
+            let group = if AUDIO_EXTENSIONS.contains(&ext.as_str()) {
+                &mut formats.audio
+            } else if PLAYLIST_EXTENSIONS.contains(&ext.as_str()) {
+                &mut formats.playlist
+            } else if SUBTITLE_EXTENSIONS.contains(&ext.as_str()) {
+                &mut formats.subtitle
+            } else {
+                &mut formats.video
+            };
+            group.push(ext);
+        }
+
+        formats
+    }
+
+    /// 可以播放的音视频扩展名
+    pub fn media_extensions(&self) -> Vec<&str> {
+        self.video
+            .iter()
+            .chain(self.audio.iter())
+            .map(|ext| ext.as_str())
+            .collect()
+    }
+}
+
+/// 所有音视频 demuxer 的扩展名 (小写, 已排序去重), 不包括 SKIPPED_DEMUXERS
+pub fn demuxer_extensions() -> Vec<String> {
+    let mut extensions = Vec::new();
+    let mut opaque: *mut c_void = std::ptr::null_mut();
+
+    loop {
+        let demuxer = unsafe { ffi::av_demuxer_iterate(&mut opaque) };
+        if demuxer.is_null() {
+            break;
+        }
+        let name = unsafe { (*demuxer).name };
+        let name = if name.is_null() {
+            Default::default()
+        } else {
+            unsafe { CStr::from_ptr(name) }.to_string_lossy()
+        };
+        // 单张图片的 demuxer 都叫 xxx_pipe, 例如 png_pipe, jpeg_pipe
+        if SKIPPED_DEMUXERS.contains(&name.as_ref()) || name.ends_with("_pipe") {
+            continue;
+        }
+        // extensions 是逗号分隔的字符串, 例如 "mov,mp4,m4a,3gp,3g2,mj2", 可能为空
+        let exts = unsafe { (*demuxer).extensions };
+        if exts.is_null() {
+            if let Some((_, fallback)) = FALLBACK_EXTENSIONS.iter().find(|(n, _)| *n == name) {
+                extensions.extend(fallback.iter().map(|ext| ext.to_string()));
+            }
+            continue;
+        }
+        let exts = unsafe { CStr::from_ptr(exts) }.to_string_lossy();
+        extensions.extend(
+            exts.split(',')
+                .map(|ext| ext.trim().to_lowercase())
+                .filter(|ext| !ext.is_empty()),
+        );
+    }
+
+    extensions.sort_unstable();
+    extensions.dedup();
+    extensions
+}
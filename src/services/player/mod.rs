@@ -1,5 +1,6 @@
 use std::fmt::Debug;
-use std::sync::atomic::AtomicBool;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::Arc;
 
 use std::time::{Duration, Instant};
@@ -8,15 +9,18 @@ use std::{collections::LinkedList, sync::atomic::Ordering};
 use crossbeam_channel::{SendError, Sender, TrySendError};
 use parking_lot::{Condvar, Mutex, RwLock};
 use rsmpeg::avcodec::AVPacket;
+use rsmpeg::ffi;
+use serde::{Deserialize, Serialize};
 
-use crate::defines::{VIDEO_SYNC_THRESHOLD_MAX, VIDEO_SYNC_THRESHOLD_MIN};
+use crate::defines::{VIDEO_DROP_THRESHOLD, VIDEO_SYNC_THRESHOLD_MAX, VIDEO_SYNC_THRESHOLD_MIN};
 use crate::error::{PlayerError, Result};
 
-use self::audio::{AudioDevice, AudioFrame};
-use self::video::VideoFrame;
+use self::audio::{output_devices, AudioDevice, AudioFrame, AudioTap, GainSource, OutputGain};
+use self::video::{rotation_filter, RawFrame, VideoFrame};
 
 pub mod audio;
 pub mod demux;
+pub mod formats;
 pub mod play;
 pub mod player;
 pub mod stream;
@@ -27,6 +31,34 @@ pub enum Command {
     Pause(bool),
     Mute(bool),
     Volume(f32),
+    /// 跳转到指定位置(秒)
+    Seek(f64),
+    /// 播放速度
+    Speed(f64),
+    /// 视频滤镜, 例如 "yadif,hflip"
+    VideoFilter(String),
+    /// 画面顺时针旋转的角度
+    Rotation(f64),
+    /// 色彩调节
+    Color(ColorAdjust),
+    /// YUV 转 RGB 的色彩空间
+    ColorSpace(ColorSpaceOverride),
+    /// HDR 色调映射
+    ToneMapping(ToneMapping),
+    /// 画面在屏幕上显示的大小(像素), 视频帧直接缩放到这个大小
+    DisplaySize(i32, i32),
+    /// 缩放算法
+    Scaler(ScalerQuality),
+    /// 切换音频设备(名字), None 为默认设备
+    AudioDevice(Option<String>),
+    /// 均衡器
+    Equalizer(Equalizer),
+    /// 响度均衡
+    Loudness(Loudness),
+    /// 声道混合
+    Downmix(Downmix),
+    /// 音频延迟(秒), 正数表示声音比画面晚
+    AudioDelay(f64),
 }
 
 #[derive(Debug, Clone)]
@@ -37,6 +69,12 @@ pub enum PlayState {
     Terminated,
     Pausing(bool),
     Video(VideoFrame),
+    /// 音频设备(名字)断开, 已切换到默认设备
+    AudioDeviceLost(String),
+    /// 设置中选择的音频设备(名字)重新连接, 已切换回该设备
+    AudioDeviceRestored(String),
+    /// 视频滤镜创建失败(FFmpeg 的错误信息), 整个滤镜链都没有生效
+    FilterError(String),
     Error(PlayerError),
 }
 
@@ -55,6 +93,499 @@ impl PartialEq for PlayState {
     }
 }
 
+/// 打开文件后得到的媒体信息
+#[derive(Debug, Clone, Default)]
+pub struct MediaInfo {
+    /// 时长(秒), 未知时为 None
+    pub duration: Option<f64>,
+    /// 文件中所有的音轨
+    pub audio_tracks: Vec<AudioTrack>,
+    /// 正在播放的音轨的流索引
+    pub audio_track: Option<usize>,
+    /// 视频需要顺时针旋转的角度, 来自视频流的 display matrix 或 rotate 标签
+    pub rotation: f64,
+    /// 有视频流, 没有时显示音频可视化
+    pub has_video: bool,
+}
+
+/// 色彩调节, 使用 eq 和 hue 滤镜
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorAdjust {
+    /// 亮度 [-1, 1]
+    pub brightness: f32,
+    /// 对比度 [0, 2]
+    pub contrast: f32,
+    /// 饱和度 [0, 3]
+    pub saturation: f32,
+    /// 伽马 [0.1, 3]
+    pub gamma: f32,
+    /// 色相(度) [-180, 180]
+    pub hue: f32,
+}
+
+impl Default for ColorAdjust {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 1.0,
+            saturation: 1.0,
+            gamma: 1.0,
+            hue: 0.0,
+        }
+    }
+}
+
+impl ColorAdjust {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// 对应的滤镜, 可以通过滤镜命令修改参数
+    pub fn filter(&self) -> String {
+        format!(
+            "eq@color=brightness={}:contrast={}:saturation={}:gamma={},hue@color=h={}",
+            self.brightness, self.contrast, self.saturation, self.gamma, self.hue
+        )
+    }
+
+    /// 修改参数的滤镜命令 (目标滤镜, 命令, 参数)
+    pub fn commands(&self) -> [(&'static str, &'static str, f32); 5] {
+        [
+            ("eq@color", "brightness", self.brightness),
+            ("eq@color", "contrast", self.contrast),
+            ("eq@color", "saturation", self.saturation),
+            ("eq@color", "gamma", self.gamma),
+            ("hue@color", "h", self.hue),
+        ]
+    }
+}
+
+/// 10 段均衡器, 每段一个 equalizer 滤镜
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Equalizer {
+    /// 每段的增益(dB)
+    pub gains: [f32; 10],
+}
+
+impl Equalizer {
+    /// 每段的中心频率(Hz), 按倍频程分布
+    pub const FREQUENCIES: [u32; 10] = [31, 62, 125, 250, 500, 1000, 2000, 4000, 8000, 16000];
+    /// 增益的范围(dB)
+    pub const MAX_GAIN: f32 = 12.0;
+
+    pub fn new(gains: [f32; 10]) -> Self {
+        Self { gains }
+    }
+
+    /// 所有增益都是 0 时, 不需要滤镜
+    pub fn is_flat(&self) -> bool {
+        self.gains.iter().all(|gain| *gain == 0.0)
+    }
+
+    /// 对应的滤镜, 可以通过滤镜命令修改增益
+    pub fn filter(&self) -> String {
+        Self::FREQUENCIES
+            .iter()
+            .zip(self.gains)
+            .enumerate()
+            .map(|(i, (frequency, gain))| {
+                format!("equalizer@eq{}=f={}:t=o:w=1:g={}", i, frequency, gain)
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// 修改增益的滤镜命令 (目标滤镜, 命令, 参数)
+    pub fn commands(&self) -> Vec<(String, &'static str, f32)> {
+        self.gains
+            .iter()
+            .enumerate()
+            .map(|(i, gain)| (format!("equalizer@eq{}", i), "g", *gain))
+            .collect()
+    }
+}
+
+/// 响度均衡的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum LoudnessMode {
+    #[default]
+    Off,
+    /// 使用 ReplayGain 的单曲增益
+    TrackGain,
+    /// 使用 ReplayGain 的专辑增益, 没有时使用单曲增益
+    AlbumGain,
+    /// EBU R128 响度标准化 (loudnorm 滤镜)
+    Loudnorm,
+    /// 动态音量标准化 (dynaudnorm 滤镜)
+    Dynamic,
+}
+
+impl LoudnessMode {
+    pub const ALL: [LoudnessMode; 5] = [
+        Self::Off,
+        Self::TrackGain,
+        Self::AlbumGain,
+        Self::Loudnorm,
+        Self::Dynamic,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "关闭",
+            Self::TrackGain => "ReplayGain 单曲",
+            Self::AlbumGain => "ReplayGain 专辑",
+            Self::Loudnorm => "EBU R128",
+            Self::Dynamic => "动态",
+        }
+    }
+}
+
+/// 响度均衡, 让不同文件的音量接近
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Loudness {
+    pub mode: LoudnessMode,
+    /// 目标响度(LUFS)
+    pub target: f32,
+    /// 前置放大(dB), 在均衡后再调整音量
+    pub preamp: f32,
+}
+
+impl Default for Loudness {
+    fn default() -> Self {
+        Self {
+            mode: LoudnessMode::Off,
+            target: Self::REPLAY_GAIN_REFERENCE,
+            preamp: 0.0,
+        }
+    }
+}
+
+impl Loudness {
+    /// ReplayGain 2.0 的参考响度(LUFS)
+    pub const REPLAY_GAIN_REFERENCE: f32 = -18.0;
+
+    /// 对应的滤镜, 不需要时为 None
+    pub fn filter(&self, replay_gain: Option<&ReplayGain>) -> Option<String> {
+        let preamp = (self.preamp != 0.0).then(|| format!("volume={}dB", self.preamp));
+        let filters: Vec<String> = match self.mode {
+            LoudnessMode::Off => return None,
+            LoudnessMode::TrackGain | LoudnessMode::AlbumGain => {
+                let album = self.mode == LoudnessMode::AlbumGain;
+                // 没有 ReplayGain 信息时, 只使用前置放大
+                let (gain, peak) = match replay_gain.and_then(|rg| rg.gain(album)) {
+                    Some(gain) => gain,
+                    None => return preamp,
+                };
+                let mut gain = gain + (self.target - Self::REPLAY_GAIN_REFERENCE) + self.preamp;
+                // 防止削波
+                if let Some(peak) = peak.filter(|peak| *peak > 0.0) {
+                    gain = gain.min(-20.0 * peak.log10());
+                }
+                vec![format!("volume={}dB", gain)]
+            }
+            LoudnessMode::Loudnorm => [
+                Some(format!("loudnorm=I={}:TP=-1.5:LRA=11", self.target)),
+                preamp,
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            LoudnessMode::Dynamic => [Some("dynaudnorm".to_string()), preamp]
+                .into_iter()
+                .flatten()
+                .collect(),
+        };
+        Some(filters.join(","))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum DownmixMode {
+    /// 设备支持的声道原样输出, 多出的声道按标准系数混合到设备的声道
+    #[default]
+    Auto,
+    /// 总是混合为立体声
+    Stereo,
+    /// 混合为立体声, 提升中置声道(对白) 并压缩动态范围, 适合小音量收听
+    Night,
+    /// 耳机虚拟环绕
+    Headphone,
+}
+
+impl DownmixMode {
+    pub const ALL: [DownmixMode; 4] = [Self::Auto, Self::Stereo, Self::Night, Self::Headphone];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "自动",
+            Self::Stereo => "立体声",
+            Self::Night => "夜间模式",
+            Self::Headphone => "耳机环绕",
+        }
+    }
+}
+
+/// 声道混合, 决定多声道的声音如何输出到设备
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Downmix {
+    pub mode: DownmixMode,
+    /// 耳机环绕使用的 SOFA (HRTF) 文件, 为空或不存在时使用交叉馈送模拟
+    pub sofa_file: String,
+}
+
+impl Downmix {
+    /// 对应的滤镜, 输出的声道由之后的 aformat 转换为设备的声道, 不需要时为 None
+    pub fn filter(&self) -> Option<String> {
+        match self.mode {
+            DownmixMode::Auto => None,
+            DownmixMode::Stereo => Some("aresample=ocl=stereo".to_string()),
+            DownmixMode::Night => Some(
+                "aresample=ocl=stereo:clev=1.414:slev=0.5:lfe_mix_level=0,\
+                 acompressor=threshold=-24dB:ratio=4:makeup=6dB"
+                    .to_string(),
+            ),
+            DownmixMode::Headphone => {
+                if !self.sofa_file.is_empty() && Path::new(&self.sofa_file).is_file() {
+                    // 滤镜参数中的 \ 和 : 需要转义
+                    let sofa = self.sofa_file.replace('\\', "/").replace(':', "\\\\:");
+                    Some(format!("sofalizer=sofa={}:type=freq", sofa))
+                } else {
+                    Some("aresample=ocl=stereo:slev=1,crossfeed=strength=0.4".to_string())
+                }
+            }
+        }
+    }
+}
+
+/// 文件中记录的 ReplayGain 信息, 增益单位是 dB, 峰值是线性的
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReplayGain {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// (增益, 峰值), 没有专辑增益时使用单曲增益
+    fn gain(&self, album: bool) -> Option<(f32, Option<f32>)> {
+        match (album, self.album_gain) {
+            (true, Some(gain)) => Some((gain, self.album_peak)),
+            _ => self.track_gain.map(|gain| (gain, self.track_peak)),
+        }
+    }
+}
+
+/// YUV 转 RGB 使用的矩阵
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum YuvMatrix {
+    /// 使用视频中记录的值, 没有记录时根据分辨率推测
+    #[default]
+    Auto,
+    Bt601,
+    Bt709,
+    Smpte240m,
+    Bt2020,
+}
+
+impl YuvMatrix {
+    pub const ALL: [YuvMatrix; 5] = [
+        Self::Auto,
+        Self::Bt601,
+        Self::Bt709,
+        Self::Smpte240m,
+        Self::Bt2020,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "自动",
+            Self::Bt601 => "BT.601",
+            Self::Bt709 => "BT.709",
+            Self::Smpte240m => "SMPTE 240M",
+            Self::Bt2020 => "BT.2020",
+        }
+    }
+}
+
+/// YUV 的取值范围
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum YuvRange {
+    /// 使用视频中记录的值, 没有记录时为有限范围
+    #[default]
+    Auto,
+    /// 有限范围 (16-235), 大多数视频
+    Limited,
+    /// 完整范围 (0-255), JPEG 等
+    Full,
+}
+
+impl YuvRange {
+    pub const ALL: [YuvRange; 3] = [Self::Auto, Self::Limited, Self::Full];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "自动",
+            Self::Limited => "有限范围",
+            Self::Full => "完整范围",
+        }
+    }
+}
+
+/// 调试用, 覆盖视频中记录的色彩空间
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ColorSpaceOverride {
+    pub matrix: YuvMatrix,
+    pub range: YuvRange,
+}
+
+/// HDR 转 SDR 的色调映射
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ToneMapMode {
+    /// PQ (HDR10) 和 HLG 视频自动使用
+    #[default]
+    Auto,
+    /// 总是使用, 用于没有记录传输特性的 HDR 视频
+    On,
+    Off,
+}
+
+impl ToneMapMode {
+    pub const ALL: [ToneMapMode; 3] = [Self::Auto, Self::On, Self::Off];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Auto => "自动",
+            Self::On => "开启",
+            Self::Off => "关闭",
+        }
+    }
+}
+
+/// 色调映射算法, 对应 tonemap 滤镜的参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ToneMapAlgorithm {
+    #[default]
+    Hable,
+    Mobius,
+    Reinhard,
+}
+
+impl ToneMapAlgorithm {
+    pub const ALL: [ToneMapAlgorithm; 3] = [Self::Hable, Self::Mobius, Self::Reinhard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Hable => "hable",
+            Self::Mobius => "mobius",
+            Self::Reinhard => "reinhard",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ToneMapping {
+    pub mode: ToneMapMode,
+    pub algorithm: ToneMapAlgorithm,
+}
+
+/// 视频帧缩放到显示大小时使用的算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ScalerQuality {
+    #[default]
+    Bilinear,
+    Bicubic,
+    Lanczos,
+}
+
+impl ScalerQuality {
+    pub const ALL: [ScalerQuality; 3] = [Self::Bilinear, Self::Bicubic, Self::Lanczos];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bilinear => "双线性",
+            Self::Bicubic => "双三次",
+            Self::Lanczos => "Lanczos",
+        }
+    }
+
+    /// swscale 的 flags, 参考: https://blog.csdn.net/leixiaohua1020/article/details/12029505
+    pub fn sws_flags(&self) -> u32 {
+        match self {
+            Self::Bilinear => ffi::SWS_BILINEAR,
+            Self::Bicubic => ffi::SWS_BICUBIC,
+            Self::Lanczos => ffi::SWS_LANCZOS,
+        }
+    }
+}
+
+/// 音轨信息
+#[derive(Debug, Clone, Default)]
+pub struct AudioTrack {
+    /// 流索引
+    pub stream_idx: usize,
+    /// 语言(小写), 例如 "chi", "eng"
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
+impl AudioTrack {
+    /// 界面上显示的名字, 例如 "音轨 2: 国语 [chi]"
+    pub fn name(&self, number: usize) -> String {
+        let mut name = format!("音轨 {}", number);
+        if let Some(title) = &self.title {
+            name.push_str(&format!(": {}", title));
+        }
+        if let Some(language) = &self.language {
+            name.push_str(&format!(" [{}]", language));
+        }
+        name
+    }
+}
+
+/// 打开文件时使用的选项, 来自用户设置
+#[derive(Debug, Clone, Default)]
+pub struct PlayOptions {
+    /// 优先选择的音轨语言(小写), 按优先级排列
+    pub audio_languages: Vec<String>,
+    /// 解码后的音频帧队列长度
+    pub audio_frame_queue: usize,
+    /// 解码后的视频帧队列长度
+    pub video_frame_queue: usize,
+    /// 每个流的 packet 队列最多占用的内存(字节)
+    pub packet_queue_mem: i32,
+    /// 指定播放的音轨(流索引), 优先于语言偏好
+    pub audio_track: Option<usize>,
+    /// 播放速度
+    pub speed: f64,
+    /// 视频滤镜, 为空时不使用滤镜
+    pub video_filter: String,
+    /// 画面顺时针旋转的角度, None 时使用视频流中记录的角度
+    pub rotation: Option<f64>,
+    pub color: ColorAdjust,
+    pub color_space: ColorSpaceOverride,
+    pub tone_mapping: ToneMapping,
+    pub scaler: ScalerQuality,
+    /// 画面在屏幕上显示的大小(像素), 切换文件或音轨时沿用, 界面只在大小改变时才会通知
+    pub display_size: Option<(i32, i32)>,
+    /// 音频设备的名字, None 为默认设备
+    pub audio_device: Option<String>,
+    pub equalizer: Equalizer,
+    pub loudness: Loudness,
+    pub downmix: Downmix,
+    /// 音频延迟(秒), 正数表示声音比画面晚
+    pub audio_delay: f64,
+    /// 音量, 0.0 ~ VOLUME_MAX
+    pub volume: f32,
+    pub mute: bool,
+}
+
 pub enum StreamType {
     Video,
     Audio,
@@ -70,6 +601,8 @@ pub struct PlayControl {
     abort_request: Arc<AtomicBool>,
     /// 暂停播放
     pause: Pause,
+    /// 跳转序号, 每次跳转后加一, 序号不同的 packet 和 帧 都需要丢弃
+    serial: Arc<AtomicUsize>,
     /*
         解封装
     */
@@ -82,8 +615,30 @@ pub struct PlayControl {
     video_frame_tx: Sender<VideoFrame>,
     /// 视频播放线程完成
     video_finished: Arc<AtomicBool>,
+    /// 视频滤镜, 改变后解码线程会重新创建滤镜图
+    video_filter: Arc<RwLock<String>>,
+    /// 画面顺时针旋转的角度, 在其他滤镜之前旋转
+    rotation: Arc<RwLock<f64>>,
+    /// 色彩调节, 改变后通过滤镜命令修改, 不需要重新创建滤镜图
+    color: Arc<RwLock<ColorAdjust>>,
+    /// 覆盖视频中记录的色彩空间
+    color_space: Arc<RwLock<ColorSpaceOverride>>,
+    /// HDR 色调映射, 改变后解码线程会重新创建滤镜图
+    tone_mapping: Arc<RwLock<ToneMapping>>,
+    /// 画面在屏幕上显示的大小(像素), None 时使用视频的原始大小
+    display_size: Arc<RwLock<Option<(i32, i32)>>>,
+    /// 缩放算法
+    scaler: Arc<RwLock<ScalerQuality>>,
+    /// 最后显示的一帧 滤镜之前的数据, 暂停时用于重新生成画面
+    last_frame: Arc<RwLock<Option<Arc<RawFrame>>>>,
+    /// 设置中选择的音频设备, 断开后重新连接时切换回来
+    preferred_audio_device: Arc<RwLock<Option<String>>>,
+    /// 设备检查线程最近一次获取到的音频设备列表
+    audio_devices: Arc<RwLock<Vec<String>>>,
     /// 控制同步
     video_clock: Arc<RwLock<Clock>>,
+    /// 解码 / 丢弃 / 显示 的帧数
+    frame_stats: Arc<FrameStats>,
     /*
         音频
     */
@@ -93,8 +648,22 @@ pub struct PlayControl {
     audio_finished: Arc<AtomicBool>,
     /// 音频包解码后得到的音频帧转换成的 音频采样数据
     audio_frame_tx: Sender<AudioFrame>,
-    /// 音量控制
-    volume: Arc<RwLock<f32>>,
+    /// 音量和静音, 在输出阶段调节
+    gain: Arc<OutputGain>,
+    /// 最近播放的采样, 用于音频可视化
+    audio_tap: Arc<AudioTap>,
+    /// 播放速度
+    speed: Arc<RwLock<f64>>,
+    /// 均衡器, 改变后通过滤镜命令修改, 不需要重新创建滤镜图
+    equalizer: Arc<RwLock<Equalizer>>,
+    /// 响度均衡, 改变后重新创建滤镜图
+    loudness: Arc<RwLock<Loudness>>,
+    /// 正在播放的音轨的 ReplayGain 信息
+    replay_gain: Arc<RwLock<Option<ReplayGain>>>,
+    /// 声道混合, 改变后重新创建滤镜图
+    downmix: Arc<RwLock<Downmix>>,
+    /// 音频延迟(秒), 视频同步到 音频时钟 + 音频延迟
+    audio_delay: Arc<RwLock<f64>>,
     /// 控制同步
     audio_clock: Arc<RwLock<Clock>>,
 }
@@ -118,37 +687,196 @@ impl PlayControl {
             start,
             abort_request,
             pause: Pause::default(),
+            serial: Arc::new(AtomicUsize::new(0)),
             demux_finished,
             video_finished,
             video_frame_tx,
             video_clock,
+            frame_stats: Arc::new(FrameStats::default()),
             audio_dev,
             audio_frame_tx,
             audio_finished,
             audio_clock,
-            volume: Arc::new(RwLock::new(1.0)),
+            gain: Arc::new(OutputGain::new(1.0, false)),
+            audio_tap: Arc::new(AudioTap::default()),
+            speed: Arc::new(RwLock::new(1.0)),
+            equalizer: Arc::new(RwLock::new(Equalizer::default())),
+            loudness: Arc::new(RwLock::new(Loudness::default())),
+            replay_gain: Arc::new(RwLock::new(None)),
+            downmix: Arc::new(RwLock::new(Downmix::default())),
+            audio_delay: Arc::new(RwLock::new(0.0)),
+            video_filter: Arc::new(RwLock::new(String::new())),
+            rotation: Arc::new(RwLock::new(0.0)),
+            color: Arc::new(RwLock::new(ColorAdjust::default())),
+            color_space: Arc::new(RwLock::new(ColorSpaceOverride::default())),
+            tone_mapping: Arc::new(RwLock::new(ToneMapping::default())),
+            display_size: Arc::new(RwLock::new(None)),
+            scaler: Arc::new(RwLock::new(ScalerQuality::default())),
+            last_frame: Arc::new(RwLock::new(None)),
+            preferred_audio_device: Arc::new(RwLock::new(None)),
+            audio_devices: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
     /// 设置静音
     pub fn set_mute(&self, mute: bool) {
-        self.audio_dev.write().set_mute(mute);
+        self.gain.set_mute(mute);
     }
 
-    /// 设置音量大小
+    /// 设置音量大小, 0.0 ~ VOLUME_MAX
     pub fn set_volume(&self, volume: f32) {
-        *self.volume.write() = volume;
+        self.gain.set_volume(volume);
+    }
+
+    /// 开始播放前设置音量和静音, 不需要过渡
+    pub fn reset_gain(&self, volume: f32, mute: bool) {
+        self.gain.reset(volume, mute);
+    }
+
+    /// 设置播放速度, 音视频时钟按照新的速度前进
+    pub fn set_speed(&self, speed: f64) {
+        *self.speed.write() = speed;
+        self.audio_clock.write().set_speed(speed);
+        self.video_clock.write().set_speed(speed);
     }
 
-    /// 当前音量
-    pub fn volume(&self) -> f32 {
-        *self.volume.read()
+    /// 当前播放速度
+    pub fn speed(&self) -> f64 {
+        *self.speed.read()
+    }
+
+    /// 设置均衡器
+    pub fn set_equalizer(&self, equalizer: Equalizer) {
+        *self.equalizer.write() = equalizer;
+    }
+
+    /// 当前均衡器
+    pub fn equalizer(&self) -> Equalizer {
+        *self.equalizer.read()
+    }
+
+    /// 设置响度均衡
+    pub fn set_loudness(&self, loudness: Loudness) {
+        *self.loudness.write() = loudness;
+    }
+
+    pub fn set_replay_gain(&self, replay_gain: Option<ReplayGain>) {
+        *self.replay_gain.write() = replay_gain;
+    }
+
+    /// 响度均衡的滤镜, 不需要时为 None
+    pub fn loudness_filter(&self) -> Option<String> {
+        self.loudness
+            .read()
+            .filter(self.replay_gain.read().as_ref())
+    }
+
+    /// 设置声道混合
+    pub fn set_downmix(&self, downmix: Downmix) {
+        *self.downmix.write() = downmix;
+    }
+
+    /// 声道混合的滤镜, 不需要时为 None
+    pub fn downmix_filter(&self) -> Option<String> {
+        self.downmix.read().filter()
+    }
+
+    /// 设置音频延迟(秒)
+    pub fn set_audio_delay(&self, delay: f64) {
+        *self.audio_delay.write() = delay;
+    }
+
+    /// 视频同步的目标时间: 音频时钟加上音频延迟, 延迟为正数时画面提前, 声音相对地延后
+    fn sync_clock(&self) -> f64 {
+        self.audio_clock.read().current() + *self.audio_delay.read()
+    }
+
+    /// 设置视频滤镜
+    pub fn set_video_filter(&self, filter: String) {
+        *self.video_filter.write() = filter;
+    }
+
+    /// 设置画面旋转的角度
+    pub fn set_rotation(&self, degrees: f64) {
+        *self.rotation.write() = degrees;
+    }
+
+    /// 设置色彩调节
+    pub fn set_color(&self, color: ColorAdjust) {
+        *self.color.write() = color;
+    }
+
+    /// 当前的色彩调节
+    pub fn color(&self) -> ColorAdjust {
+        *self.color.read()
+    }
+
+    /// 记录最后显示的一帧
+    pub fn set_last_frame(&self, frame: Option<Arc<RawFrame>>) {
+        *self.last_frame.write() = frame;
+    }
+
+    /// 最后显示的一帧, 滤镜之前的数据
+    pub fn last_frame(&self) -> Option<Arc<RawFrame>> {
+        self.last_frame.read().clone()
+    }
+
+    /// 设置 YUV 转 RGB 的色彩空间
+    pub fn set_color_space(&self, color_space: ColorSpaceOverride) {
+        *self.color_space.write() = color_space;
+    }
+
+    /// 当前 YUV 转 RGB 的色彩空间
+    pub fn color_space(&self) -> ColorSpaceOverride {
+        *self.color_space.read()
+    }
+
+    /// 设置 HDR 色调映射
+    pub fn set_tone_mapping(&self, tone_mapping: ToneMapping) {
+        *self.tone_mapping.write() = tone_mapping;
+    }
+
+    /// 当前的 HDR 色调映射
+    pub fn tone_mapping(&self) -> ToneMapping {
+        *self.tone_mapping.read()
+    }
+
+    /// 设置画面在屏幕上显示的大小(像素)
+    pub fn set_display_size(&self, width: i32, height: i32) {
+        *self.display_size.write() = Some((width, height));
+    }
+
+    /// 画面在屏幕上显示的大小(像素)
+    pub fn display_size(&self) -> Option<(i32, i32)> {
+        *self.display_size.read()
+    }
+
+    /// 设置缩放算法
+    pub fn set_scaler(&self, scaler: ScalerQuality) {
+        *self.scaler.write() = scaler;
+    }
+
+    /// 当前的缩放算法
+    pub fn scaler(&self) -> ScalerQuality {
+        *self.scaler.read()
+    }
+
+    /// 当前视频滤镜, 包括旋转, 不包括色彩调节
+    pub fn video_filter(&self) -> String {
+        let filter = self.video_filter.read();
+        match rotation_filter(*self.rotation.read()) {
+            Some(rotate) if filter.is_empty() => rotate.to_string(),
+            Some(rotate) => format!("{},{}", rotate, filter),
+            None => filter.clone(),
+        }
     }
 
     /// 设置是否取消播放
     pub fn set_abort_request(&self, abort_request: bool) {
         self.abort_request.store(abort_request, Ordering::Relaxed);
         self.audio_dev.write().stop();
+        // 暂停中的播放线程也需要唤醒后退出
+        self.pause.notify_all();
     }
 
     /// 是否取消播放
@@ -158,6 +886,9 @@ impl PlayControl {
 
     /// 设置是否暂停播放
     pub fn set_pause(&mut self, pause: bool) {
+        // 暂停期间时钟不能前进, 否则恢复播放后 视频帧都会被当作落后而丢弃
+        self.audio_clock.write().set_paused(pause);
+        self.video_clock.write().set_paused(pause);
         self.pause.set_pause(pause);
         self.audio_dev.write().set_pause(pause);
         self.state_tx.send(PlayState::Pausing(pause)).ok();
@@ -173,6 +904,22 @@ impl PlayControl {
         self.pause.wait();
     }
 
+    /// 当前的跳转序号
+    pub fn serial(&self) -> usize {
+        self.serial.load(Ordering::Relaxed)
+    }
+
+    /// 跳转时调用, 返回新的序号
+    pub fn next_serial(&self) -> usize {
+        self.serial.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// 跳转后, 音视频时钟都从新的位置开始
+    pub fn reset_clocks(&self, pts: f64) {
+        self.audio_clock.write().update(pts, 0.0);
+        self.video_clock.write().update(pts, 0.0);
+    }
+
     /// 设置音频播放线程是否完成
     pub fn set_audio_finished(&self, finished: bool) {
         self.audio_finished.store(finished, Ordering::Relaxed);
@@ -203,6 +950,62 @@ impl PlayControl {
         self.demux_finished.load(Ordering::Relaxed)
     }
 
+    /// 切换音频设备, 保持暂停状态, 已经送入旧设备的声音会丢失
+    pub fn set_audio_device(&self, name: Option<&str>) -> Result<()> {
+        let audio_dev = AudioDevice::new(name)?;
+        audio_dev.set_pause(self.pause());
+        *self.audio_dev.write() = audio_dev;
+        Ok(())
+    }
+
+    /// 设置中选择的音频设备, None 表示默认设备
+    pub fn set_preferred_audio_device(&self, name: Option<String>) {
+        *self.preferred_audio_device.write() = name;
+    }
+
+    /// 当前音频设备断开时, 切换到默认设备; 设置中选择的设备重新连接时, 切换回来. 都会通知 ui
+    pub fn check_audio_device(&self) {
+        // 枚举设备比较慢, 不能持有锁, 否则会阻塞音频播放
+        let name = self.audio_dev.read().name().to_string();
+        // 获取设备列表失败时, 不知道设备是否还在, 不切换
+        let devices = match output_devices() {
+            Some(devices) => devices,
+            None => return,
+        };
+        *self.audio_devices.write() = devices.clone();
+
+        let preferred = self.preferred_audio_device.read().clone();
+        if let Some(preferred) = preferred {
+            if preferred != name && devices.contains(&preferred) {
+                log::info!("音频设备 {} 已重新连接, 切换回该设备", preferred);
+                match self.set_audio_device(Some(&preferred)) {
+                    Ok(_) => {
+                        self.state_tx
+                            .send(PlayState::AudioDeviceRestored(preferred))
+                            .ok();
+                    }
+                    Err(e) => {
+                        log::error!("打开音频设备 {} 失败, E: {}", preferred, e);
+                    }
+                }
+                return;
+            }
+        }
+
+        if devices.contains(&name) {
+            return;
+        }
+        log::warn!("音频设备 {} 已断开, 切换到默认设备", name);
+        match self.set_audio_device(None) {
+            Ok(_) => {
+                self.state_tx.send(PlayState::AudioDeviceLost(name)).ok();
+            }
+            Err(e) => {
+                log::error!("打开默认音频设备失败, E: {}", e);
+            }
+        }
+    }
+
     /// 获取声音设备的默认配置
     pub fn audio_default_config(&self) -> cpal::SupportedStreamConfig {
         self.audio_dev.read().default_config()
@@ -230,17 +1033,35 @@ impl PlayControl {
     pub fn play_audio(&self, frame: AudioFrame) -> Result<()> {
         // 更新音频时钟
         let delay = self.update_audio_clock(frame.pts(), frame.duration());
+        // 记录最近的采样, 用于音频可视化
+        self.audio_tap.push(&frame);
         // 播放
-        self.audio_dev.write().play_source(frame);
+        self.audio_dev
+            .write()
+            .play_source(GainSource::new(frame, self.gain.clone()));
         // 休眠
         spin_sleep::sleep(Duration::from_secs_f64(delay));
         Ok(())
     }
 
+    /// 视频帧 落后于音频的时间(秒), 负数表示超前
+    pub fn video_lag(&self, pts: f64) -> f64 {
+        self.sync_clock() - pts
+    }
+
+    /// 视频帧落后太多, 应该丢弃
+    pub fn video_too_late(&self, pts: f64) -> bool {
+        self.video_lag(pts) > VIDEO_DROP_THRESHOLD * self.speed()
+    }
+
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
     /// 播放视频帧
     pub fn play_video(&self, frame: VideoFrame) -> Result<()> {
-        // 更新视频时钟
-        let delay = self.update_video_clock(frame.pts(), frame.duration());
+        // 更新视频时钟, 帧的持续时间按照播放速度缩短或延长
+        let delay = self.update_video_clock(frame.pts(), frame.duration()) / self.speed();
         // 播放
         match self.send_state(PlayState::Video(frame)) {
             Ok(_) | Err(TrySendError::Full(_)) => {}
@@ -253,6 +1074,17 @@ impl PlayControl {
         Ok(())
     }
 
+    /// 用于查询播放进度
+    pub fn play_position(&self) -> PlayPosition {
+        PlayPosition {
+            audio_clock: self.audio_clock.clone(),
+            video_clock: self.video_clock.clone(),
+            frame_stats: self.frame_stats.clone(),
+            audio_tap: self.audio_tap.clone(),
+            audio_devices: self.audio_devices.clone(),
+        }
+    }
+
     pub fn current_audio_clock(&self) -> f64 {
         self.audio_clock.write().current()
     }
@@ -270,7 +1102,7 @@ impl PlayControl {
     }
 
     fn compute_video_delay(&self) -> f64 {
-        let audio_clock = self.audio_clock.read().current();
+        let audio_clock = self.sync_clock();
         let video_clock = self.video_clock.read().current();
         let duration = self.video_clock.read().duration();
         let diff = video_clock - audio_clock;
@@ -291,11 +1123,87 @@ impl PlayControl {
     }
 }
 
+/// 在 ui 线程中查询播放进度 和 帧统计, 只持有音视频时钟, 不影响播放线程的退出
+#[derive(Clone)]
+pub struct PlayPosition {
+    audio_clock: Arc<RwLock<Clock>>,
+    video_clock: Arc<RwLock<Clock>>,
+    frame_stats: Arc<FrameStats>,
+    audio_tap: Arc<AudioTap>,
+    audio_devices: Arc<RwLock<Vec<String>>>,
+}
+
+impl PlayPosition {
+    /// 当前播放位置(秒), 取 音频时钟 和 视频时钟 中较大的显示时间
+    pub fn position(&self) -> f64 {
+        let audio_pts = self.audio_clock.read().pts();
+        let video_pts = self.video_clock.read().pts();
+        audio_pts.max(video_pts)
+    }
+
+    pub fn frame_counts(&self) -> FrameCounts {
+        self.frame_stats.counts()
+    }
+
+    /// 最近播放的采样(单声道) 和 采样率
+    pub fn audio_samples(&self) -> (Vec<f32>, u32) {
+        self.audio_tap.samples()
+    }
+
+    /// 设备检查线程最近一次获取到的音频设备列表
+    pub fn audio_devices(&self) -> Vec<String> {
+        self.audio_devices.read().clone()
+    }
+}
+
+/// 视频帧统计, 解码线程 和 播放线程 更新
+#[derive(Default)]
+pub struct FrameStats {
+    decoded: AtomicUsize,
+    dropped: AtomicUsize,
+    presented: AtomicUsize,
+}
+
+impl FrameStats {
+    pub fn add_decoded(&self) {
+        self.decoded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_presented(&self) {
+        self.presented.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn counts(&self) -> FrameCounts {
+        FrameCounts {
+            decoded: self.decoded.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            presented: self.presented.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// 某一时刻的帧统计
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameCounts {
+    /// 解码的帧数
+    pub decoded: usize,
+    /// 落后太多而丢弃的帧数, 不包括解码器跳过的非参考帧
+    pub dropped: usize,
+    /// 显示的帧数
+    pub presented: usize,
+}
+
 pub struct PacketQueue {
     queue: LinkedList<AVPacket>,
     mem_size: i32,
     max_mem_size: i32,
     stream_idx: i32,
+    /// 队列中 packet 的跳转序号
+    serial: usize,
 }
 
 impl PacketQueue {
@@ -305,6 +1213,7 @@ impl PacketQueue {
             mem_size: 0,
             max_mem_size,
             stream_idx,
+            serial: 0,
         }
     }
 
@@ -312,6 +1221,17 @@ impl PacketQueue {
         self.stream_idx
     }
 
+    pub fn serial(&self) -> usize {
+        self.serial
+    }
+
+    /// 跳转时清空队列, 之后加入的 packet 使用新的序号
+    pub fn flush(&mut self, serial: usize) {
+        self.queue.clear();
+        self.mem_size = 0;
+        self.serial = serial;
+    }
+
     #[inline]
     pub fn is_full(&self) -> bool {
         self.mem_size >= self.max_mem_size
@@ -486,6 +1406,10 @@ pub struct Clock {
     duration: f64,
     /// 当前帧的更新时间
     last_update: Duration,
+    /// 播放速度, 时钟每秒前进的秒数
+    speed: f64,
+    /// 暂停时 时钟停在 pts
+    paused: bool,
 }
 
 impl Clock {
@@ -496,11 +1420,33 @@ impl Clock {
             pts: 0.0,
             duration: 0.0,
             last_update,
+            speed: 1.0,
+            paused: false,
         }
     }
 
     pub fn current(&self) -> f64 {
-        self.pts + (self.start.elapsed() - self.last_update).as_secs_f64()
+        if self.paused {
+            return self.pts;
+        }
+        self.pts + (self.start.elapsed() - self.last_update).as_secs_f64() * self.speed
+    }
+
+    /// 暂停时停在当前时间, 恢复后从暂停的位置继续
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.paused == paused {
+            return;
+        }
+        self.pts = self.current();
+        self.last_update = self.start.elapsed();
+        self.paused = paused;
+    }
+
+    /// 修改速度时, 从当前时间开始按新的速度计算
+    pub fn set_speed(&mut self, speed: f64) {
+        self.pts = self.current();
+        self.last_update = self.start.elapsed();
+        self.speed = speed;
     }
 
     pub fn update(&mut self, pts: f64, duration: f64) {
@@ -512,4 +1458,8 @@ impl Clock {
     pub fn duration(&self) -> f64 {
         self.duration
     }
+
+    pub fn pts(&self) -> f64 {
+        self.pts
+    }
 }
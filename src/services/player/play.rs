@@ -1,29 +1,58 @@
 use std::sync::{atomic::AtomicBool, Arc};
 
 use crossbeam_channel::{bounded, Receiver, Sender};
+use rsmpeg::ffi;
 
-use crate::{
-    defines::{AUDIO_FRAME_QUEUE_SIZE, VIDEO_FRAME_QUEUE_SIZE},
-    error::Result,
-};
+use crate::error::Result;
 
 use super::{
-    audio::{audio_decode_thread, audio_play_thread, AudioFrame},
-    demux::{demux_init, demux_thread, DemuxContext},
+    audio::{audio_decode_thread, audio_device_thread, audio_play_thread, AudioFrame},
+    demux::{
+        audio_tracks, demux_init, demux_thread, seek_input, stream_replay_gain, stream_rotation,
+        DemuxContext,
+    },
     video::{video_decode_thread, video_play_thread, VideoFrame},
-    Command, PlayState, StreamType,
+    Command, MediaInfo, PlayOptions, PlayPosition, PlayState, StreamType,
 };
 
+/// start: 开始播放的位置(秒)
 pub fn play(
     filename: String,
+    start: f64,
+    options: &PlayOptions,
     cmd_rx: Receiver<Command>,
     state_tx: Sender<PlayState>,
     abort_request: Arc<AtomicBool>,
-) -> Result<()> {
-    let (audio_frame_tx, audio_frame_queue) = bounded::<AudioFrame>(AUDIO_FRAME_QUEUE_SIZE);
-    let (video_frame_tx, video_frame_queue) = bounded::<VideoFrame>(VIDEO_FRAME_QUEUE_SIZE);
+) -> Result<(MediaInfo, PlayPosition)> {
+    let (audio_frame_tx, audio_frame_queue) = bounded::<AudioFrame>(options.audio_frame_queue);
+    let (video_frame_tx, video_frame_queue) = bounded::<VideoFrame>(options.video_frame_queue);
+
+    let (mut ifmt_ctx, vdec, adec) = demux_init(filename, options)?;
+
+    // duration 的单位是 AV_TIME_BASE, 未知时是 AV_NOPTS_VALUE
+    let replay_gain = adec
+        .as_ref()
+        .and_then(|(stream_idx, _)| ifmt_ctx.streams().get(*stream_idx))
+        .and_then(|stream| stream_replay_gain(&stream));
+    log::info!("ReplayGain: {:?}", replay_gain);
 
-    let (ifmt_ctx, vdec, adec) = demux_init(filename)?;
+    let media_info = MediaInfo {
+        duration: (ifmt_ctx.duration > 0)
+            .then(|| ifmt_ctx.duration as f64 / ffi::AV_TIME_BASE as f64),
+        audio_tracks: audio_tracks(&ifmt_ctx),
+        audio_track: adec.as_ref().map(|(stream_idx, _)| *stream_idx),
+        has_video: vdec.is_some(),
+        rotation: vdec
+            .as_ref()
+            .and_then(|(stream_idx, _)| ifmt_ctx.streams().get(*stream_idx))
+            .map(|stream| stream_rotation(&stream))
+            .unwrap_or_default(),
+    };
+
+    // 在启动各个线程之前跳转, 就不需要清空队列和解码器
+    if start > 0.0 {
+        seek_input(&mut ifmt_ctx, start);
+    }
 
     let (mut demux_ctx, play_ctrl) = DemuxContext::new(
         ifmt_ctx,
@@ -31,7 +60,25 @@ pub fn play(
         audio_frame_tx,
         video_frame_tx,
         abort_request,
+        options.packet_queue_mem,
+        options.audio_device.as_deref(),
     );
+    play_ctrl.reset_gain(options.volume, options.mute);
+    play_ctrl.set_speed(options.speed);
+    play_ctrl.set_equalizer(options.equalizer);
+    play_ctrl.set_loudness(options.loudness);
+    play_ctrl.set_replay_gain(replay_gain);
+    play_ctrl.set_downmix(options.downmix.clone());
+    play_ctrl.set_audio_delay(options.audio_delay);
+    play_ctrl.set_video_filter(options.video_filter.clone());
+    play_ctrl.set_rotation(options.rotation.unwrap_or(media_info.rotation));
+    play_ctrl.set_color(options.color);
+    play_ctrl.set_color_space(options.color_space);
+    play_ctrl.set_tone_mapping(options.tone_mapping);
+    play_ctrl.set_scaler(options.scaler);
+    if let Some((width, height)) = options.display_size {
+        play_ctrl.set_display_size(width, height);
+    }
 
     let video_decode_ctx = demux_ctx.build_decode_ctx(vdec, StreamType::Video);
     let audio_decode_ctx = demux_ctx.build_decode_ctx(adec, StreamType::Audio);
@@ -50,6 +97,12 @@ pub fn play(
                 log::info!("{}", e.to_string());
             }
         });
+
+        // 音频设备检查线程
+        let play_ctrl0 = play_ctrl.clone();
+        std::thread::spawn(move || {
+            audio_device_thread(play_ctrl0);
+        });
     }
 
     if let Some(decode_ctx) = video_decode_ctx {
@@ -75,5 +128,5 @@ pub fn play(
         demux_thread(demux_ctx, cmd_rx);
     });
 
-    Ok(())
+    Ok((media_info, play_ctrl.play_position()))
 }
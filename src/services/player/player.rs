@@ -7,12 +7,16 @@ use crossbeam_channel::{bounded, Receiver, Sender, TryRecvError};
 
 use crate::error::Result;
 
-use super::{play::play, Command, PlayState};
+use super::{
+    play::play, ColorAdjust, ColorSpaceOverride, Command, Downmix, Equalizer, FrameCounts,
+    Loudness, MediaInfo, PlayOptions, PlayPosition, PlayState, ScalerQuality, ToneMapping,
+};
 
 pub struct Player {
     cmd_tx: Sender<Command>,
     state_rx: Receiver<PlayState>,
     abort_request: Arc<AtomicBool>,
+    play_position: Option<PlayPosition>,
 }
 
 impl Drop for Player {
@@ -33,20 +37,56 @@ impl Default for Player {
             cmd_tx,
             state_rx,
             abort_request,
+            play_position: None,
         }
     }
 }
 impl Player {
-    pub fn play(&mut self, file: impl Into<String>) -> Result<()> {
-        let (cmd_tx, cmd_rx) = bounded::<Command>(2);
+    /// 从 start(秒) 的位置开始播放文件
+    pub fn play(
+        &mut self,
+        file: impl Into<String>,
+        start: f64,
+        options: &PlayOptions,
+    ) -> Result<MediaInfo> {
+        let (cmd_tx, cmd_rx) = bounded::<Command>(8);
         let (state_tx, state_rx) = bounded::<PlayState>(1);
 
         self.cmd_tx = cmd_tx;
         self.state_rx = state_rx;
         self.abort_request = Arc::new(AtomicBool::new(false));
+        self.play_position = None;
+
+        let (media_info, play_position) = play(
+            file.into(),
+            start,
+            options,
+            cmd_rx,
+            state_tx,
+            self.abort_request.clone(),
+        )?;
+        self.play_position = Some(play_position);
+        Ok(media_info)
+    }
+
+    /// 当前播放位置(秒)
+    pub fn position(&self) -> Option<f64> {
+        self.play_position.as_ref().map(|p| p.position())
+    }
+
+    /// 视频帧统计
+    pub fn frame_counts(&self) -> Option<FrameCounts> {
+        self.play_position.as_ref().map(|p| p.frame_counts())
+    }
 
-        play(file.into(), cmd_rx, state_tx, self.abort_request.clone())?;
-        Ok(())
+    /// 最近播放的采样(单声道) 和 采样率, 用于音频可视化
+    pub fn audio_samples(&self) -> Option<(Vec<f32>, u32)> {
+        self.play_position.as_ref().map(|p| p.audio_samples())
+    }
+
+    /// 设备检查线程最近一次获取到的音频设备列表
+    pub fn audio_devices(&self) -> Option<Vec<String>> {
+        self.play_position.as_ref().map(|p| p.audio_devices())
     }
 
     pub fn play_finished(&self) -> bool {
@@ -82,6 +122,99 @@ impl Player {
         }
     }
 
+    /// 跳转到 position(秒)
+    pub fn seek(&self, position: f64) {
+        log::info!("play service seek: {position}");
+        if let Err(e) = self.cmd_tx.try_send(Command::Seek(position)) {
+            log::error!("发送 Command::Seek 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        log::info!("play service set speed: {speed}");
+        if let Err(e) = self.cmd_tx.try_send(Command::Speed(speed)) {
+            log::error!("发送 Command::Speed 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_video_filter(&self, filter: String) {
+        log::info!("play service set video filter: {filter}");
+        if let Err(e) = self.cmd_tx.try_send(Command::VideoFilter(filter)) {
+            log::error!("发送 Command::VideoFilter 失败, E: {}", e.to_string());
+        }
+    }
+
+    /// 画面顺时针旋转的角度
+    pub fn set_rotation(&self, degrees: f64) {
+        log::info!("play service set rotation: {degrees}");
+        if let Err(e) = self.cmd_tx.try_send(Command::Rotation(degrees)) {
+            log::error!("发送 Command::Rotation 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_color(&self, color: ColorAdjust) {
+        if let Err(e) = self.cmd_tx.try_send(Command::Color(color)) {
+            log::error!("发送 Command::Color 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_color_space(&self, color_space: ColorSpaceOverride) {
+        if let Err(e) = self.cmd_tx.try_send(Command::ColorSpace(color_space)) {
+            log::error!("发送 Command::ColorSpace 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_tone_mapping(&self, tone_mapping: ToneMapping) {
+        if let Err(e) = self.cmd_tx.try_send(Command::ToneMapping(tone_mapping)) {
+            log::error!("发送 Command::ToneMapping 失败, E: {}", e.to_string());
+        }
+    }
+
+    /// 画面在屏幕上显示的大小(像素)
+    pub fn set_display_size(&self, width: i32, height: i32) {
+        if let Err(e) = self.cmd_tx.try_send(Command::DisplaySize(width, height)) {
+            log::error!("发送 Command::DisplaySize 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_scaler(&self, scaler: ScalerQuality) {
+        if let Err(e) = self.cmd_tx.try_send(Command::Scaler(scaler)) {
+            log::error!("发送 Command::Scaler 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_equalizer(&self, equalizer: Equalizer) {
+        if let Err(e) = self.cmd_tx.try_send(Command::Equalizer(equalizer)) {
+            log::error!("发送 Command::Equalizer 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_loudness(&self, loudness: Loudness) {
+        if let Err(e) = self.cmd_tx.try_send(Command::Loudness(loudness)) {
+            log::error!("发送 Command::Loudness 失败, E: {}", e.to_string());
+        }
+    }
+
+    pub fn set_downmix(&self, downmix: Downmix) {
+        if let Err(e) = self.cmd_tx.try_send(Command::Downmix(downmix)) {
+            log::error!("发送 Command::Downmix 失败, E: {}", e.to_string());
+        }
+    }
+
+    /// 音频延迟(秒), 正数表示声音比画面晚
+    pub fn set_audio_delay(&self, delay: f64) {
+        if let Err(e) = self.cmd_tx.try_send(Command::AudioDelay(delay)) {
+            log::error!("发送 Command::AudioDelay 失败, E: {}", e.to_string());
+        }
+    }
+
+    /// 切换音频设备, None 为默认设备
+    pub fn set_audio_device(&self, name: Option<String>) {
+        if let Err(e) = self.cmd_tx.try_send(Command::AudioDevice(name)) {
+            log::error!("发送 Command::AudioDevice 失败, E: {}", e.to_string());
+        }
+    }
+
     pub fn try_recv_state(&mut self) -> Option<PlayState> {
         match self.state_rx.try_recv() {
             Ok(state) => Some(state),
@@ -13,19 +13,32 @@ use crate::error::{PlayerError, Result};
 pub struct DecodeContext {
     dec_ctx: AVCodecContext,
     queue: Arc<Mutex<PacketQueue>>,
+    /// 最近送入解码器的 packet 的跳转序号
+    serial: usize,
 }
 
 impl DecodeContext {
     pub fn new(dec_ctx: AVCodecContext, queue: Arc<Mutex<PacketQueue>>) -> Self {
-        Self { dec_ctx, queue }
+        Self {
+            dec_ctx,
+            queue,
+            serial: 0,
+        }
+    }
+
+    /// 解码得到的帧的跳转序号
+    pub fn serial(&self) -> usize {
+        self.serial
     }
 
     pub fn stream_idx(&self) -> i32 {
         self.queue.lock().stream_idx()
     }
 
-    pub fn queue_pop(&self) -> Option<AVPacket> {
-        self.queue.lock().pop()
+    /// 取出一个 packet 和它的跳转序号
+    pub fn queue_pop(&self) -> Option<(AVPacket, usize)> {
+        let mut queue = self.queue.lock();
+        queue.pop().map(|pkt| (pkt, queue.serial()))
     }
 
     pub fn queue_is_empty(&self) -> bool {
@@ -76,7 +89,12 @@ pub fn decode_frame(
 
         if retry_send_packet {
             let pkt = decode_ctx.queue_pop();
-            if let Some(pkt) = pkt {
+            if let Some((pkt, serial)) = pkt {
+                // 跳转后的第一个 packet, 丢弃解码器中缓存的旧数据
+                if serial != decode_ctx.serial {
+                    unsafe { ffi::avcodec_flush_buffers(decode_ctx.dec_ctx().as_mut_ptr()) };
+                    decode_ctx.serial = serial;
+                }
                 if pkt.data as *const u8 == std::ptr::null() {
                     unsafe { ffi::avcodec_flush_buffers(decode_ctx.dec_ctx().as_mut_ptr()) };
                     continue;
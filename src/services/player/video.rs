@@ -1,15 +1,27 @@
+use std::borrow::Cow;
+use std::ffi::CString;
 use std::fmt::Debug;
+use std::path::Path;
+use std::ptr;
+use std::sync::Arc;
 
 use crossbeam_channel::Receiver;
+use rsmpeg::avfilter::AVFilterGraph;
 use rsmpeg::avutil::AVFrame;
+use rsmpeg::error::RsmpegError;
 use rsmpeg::ffi::av_q2d;
 use rsmpeg::ffi::{self, AVRational};
 use rsmpeg::swscale::SwsContext;
 
-use crate::defines::PLAY_MIN_INTERVAL;
-use crate::error::Result;
+use crate::defines::{
+    PLAY_MIN_INTERVAL, VIDEO_DROP_THRESHOLD, VIDEO_MAX_CONSECUTIVE_DROPS, VIDEO_SKIP_THRESHOLD,
+};
+use crate::error::{PlayerError, Result};
 use crate::services::player::stream::{decode_frame, DecodeContext};
-use crate::services::player::PlayControl;
+use crate::services::player::{
+    ColorAdjust, ColorSpaceOverride, PlayControl, PlayState, ToneMapMode, ToneMapping, YuvMatrix,
+    YuvRange,
+};
 
 use super::PlayFrame;
 
@@ -19,47 +31,19 @@ pub fn video_decode_thread(
     mut decode_ctx: DecodeContext,
     time_base: AVRational,
 ) {
-    // 获取需要的参数
-    let width = decode_ctx.dec_ctx().width;
-    let height = decode_ctx.dec_ctx().height;
-    let pix_fmt = decode_ctx.dec_ctx().pix_fmt;
-
-    // flags参数选择, 参考: https://blog.csdn.net/leixiaohua1020/article/details/12029505
-    let mut video_sws = SwsContext::get_context(
-        width,
-        height,
-        pix_fmt,
-        width,
-        height,
-        ffi::AVPixelFormat_AV_PIX_FMT_RGBA,
-        ffi::SWS_FAST_BILINEAR,
-    )
-    .or_else(|| {
-        log::error!("Failed to create a swscale context.");
-        None
-    })
-    .unwrap();
-
-    // 用于把 接收到的帧数据 转换成 特定格式的帧数据
-    let mut rgb_frame = AVFrame::new();
-    rgb_frame.set_format(ffi::AVPixelFormat_AV_PIX_FMT_RGBA);
-    rgb_frame.set_width(width);
-    rgb_frame.set_height(height);
-    rgb_frame
-        .alloc_buffer()
-        .map_err(|e| log::error!("frame alloc_buffer failed, error: {:?}", e))
-        .unwrap();
-
     // 帧速率(一秒播放的帧数)的倒数, 即: 一帧的播放的秒数
     let duration = 1.0 / av_q2d(decode_ctx.dec_ctx().framerate);
-    // pts dts 等时间参数的基本单位, 表示: 单位1 表示的秒数
-    let time_base = av_q2d(time_base);
+    // 滤镜图 和 RGBA 转换, 在收到第一帧后创建
+    let mut video_graph: Option<VideoGraph> = None;
+    let mut converter: Option<RgbaConverter> = None;
+    // 解码器是否正在跳过非参考帧
+    let mut skip_nonref = false;
 
-    loop {
+    'decode: loop {
         // 解码视频包, 获取视频帧
         let ret = decode_frame(&play_ctrl, &mut decode_ctx);
 
-        let raw_frame = match ret {
+        let mut raw_frame = match ret {
             Ok(Some(frame)) => frame,
             Ok(None) => {
                 break;
@@ -69,41 +53,674 @@ pub fn video_decode_thread(
                 break;
             }
         };
+        // 滤镜使用 pts, 这里用 best_effort_timestamp 代替
+        raw_frame.set_pts(raw_frame.best_effort_timestamp);
+        play_ctrl.frame_stats().add_decoded();
 
-        // 视频帧 格式转换
-        video_sws
-            .scale_frame(&raw_frame, 0, raw_frame.height, &mut rgb_frame)
-            .map_err(|e| log::error!("video sws scale_frame failed, error: {:?}", e))
-            .unwrap();
+        // 落后太多时, 解码器跳过非参考帧, 追上后恢复
+        let lag = play_ctrl.video_lag(raw_frame.pts as f64 * av_q2d(time_base));
+        if !skip_nonref && lag > VIDEO_SKIP_THRESHOLD * play_ctrl.speed() {
+            skip_nonref = true;
+            set_skip_frame(&mut decode_ctx, ffi::AVDiscard_AVDISCARD_NONREF);
+            log::info!("视频落后 {:.3} 秒, 跳过非参考帧", lag);
+        } else if skip_nonref && lag < VIDEO_DROP_THRESHOLD * play_ctrl.speed() {
+            skip_nonref = false;
+            set_skip_frame(&mut decode_ctx, ffi::AVDiscard_AVDISCARD_DEFAULT);
+            log::info!("视频已追上, 恢复解码所有帧");
+        }
 
-        // best_effort_timestamp 它是以时间基为单位, 表示 best_effort_timestamp 个时间基.
-        // 显示时间, 单位: 秒
-        let pts = raw_frame.best_effort_timestamp as f64 * time_base;
+        // 帧中没有像素宽高比时, 使用解码器中的
+        let default_sar = decode_ctx.dec_ctx().sample_aspect_ratio;
 
-        // 从新格式的视频帧中 构建rgb数据
-        let video = VideoFrame::new(
-            rgb_frame.data[0] as *const u8,
-            width as usize,
-            height as usize,
-            rgb_frame.linesize[0] as usize,
-            pts,
+        // 滤镜改变 或 跳转后, 重新创建滤镜图
+        let filter = play_ctrl.video_filter();
+        let color = play_ctrl.color();
+        let serial = decode_ctx.serial();
+        let tone_map = tone_map_filter(&raw_frame, play_ctrl.tone_mapping());
+        let rebuild = match &video_graph {
+            Some(graph) => !graph.matches(&raw_frame, &tone_map, &filter, &color, serial),
+            None => true,
+        };
+        let mut frames = vec![];
+        if rebuild {
+            if let Some(mut old) = video_graph.take() {
+                if old.size_changed(&raw_frame) {
+                    log::info!(
+                        "视频帧参数改变: {}x{} {} -> {}x{} {}",
+                        old.width,
+                        old.height,
+                        old.pix_fmt,
+                        raw_frame.width,
+                        raw_frame.height,
+                        raw_frame.format
+                    );
+                }
+                // 取出旧滤镜图中缓存的帧, 跳转前的帧直接丢弃
+                if old.serial == serial {
+                    frames = old.flush();
+                }
+            }
+            video_graph = Some(VideoGraph::new(
+                &play_ctrl, &raw_frame, time_base, tone_map, filter, color, serial,
+            ));
+        }
+        let raw = Arc::new(RawFrame {
+            frame: raw_frame.clone(),
+            time_base,
+            default_sar,
             duration,
-        );
+            serial,
+        });
+        let graph = video_graph.as_mut().unwrap();
+        graph.set_color(color);
+        frames.extend(graph.filter(raw_frame, time_base));
 
-        // 发送 rgb数据 给 video play thread
-        if let Err(_) = play_ctrl.send_video(video) {
-            log::info!("video channel disconnected");
-            break;
+        for (frame, time_base) in frames {
+            // 视频帧 格式转换, 帧的大小, 格式 或 显示大小改变时重新创建转换器
+            let color_space = play_ctrl.color_space();
+            let dst_size = scaled_size(&frame, play_ctrl.display_size());
+            let flags = play_ctrl.scaler().sws_flags();
+            if !matches!(&converter, Some(c) if c.matches(&frame, color_space, dst_size, flags)) {
+                converter = RgbaConverter::new(&frame, color_space, dst_size, flags);
+                if converter.is_none() {
+                    break 'decode;
+                }
+            }
+            let rgb_frame = match converter.as_mut().unwrap().convert(&frame) {
+                Ok(rgb_frame) => rgb_frame,
+                Err(e) => {
+                    log::error!("video sws scale_frame failed, error: {:?}", e);
+                    continue;
+                }
+            };
+
+            let mut video = build_video_frame(&frame, time_base, rgb_frame, &raw, color_space);
+            video.raw = Some(raw.clone());
+
+            // 发送 rgb数据 给 video play thread
+            if let Err(_) = play_ctrl.send_video(video) {
+                log::info!("video channel disconnected");
+                break 'decode;
+            }
         }
     }
     log::info!("视频解码线程退出");
 }
 
+/// 从滤镜输出的帧 和 它转换后的 rgb 帧 构建 VideoFrame
+fn build_video_frame(
+    frame: &AVFrame,
+    time_base: AVRational,
+    rgb_frame: &AVFrame,
+    raw: &RawFrame,
+    color_space: ColorSpaceOverride,
+) -> VideoFrame {
+    // pts 是以时间基为单位, 显示时间, 单位: 秒
+    let pts = frame.pts as f64 * av_q2d(time_base);
+
+    // 从新格式的视频帧中 构建rgb数据
+    let mut video = VideoFrame::new(
+        rgb_frame.data[0] as *const u8,
+        rgb_frame.width as usize,
+        rgb_frame.height as usize,
+        rgb_frame.linesize[0] as usize,
+        pts,
+        raw.duration,
+        raw.serial,
+    );
+    let sar = match frame.sample_aspect_ratio {
+        sar if sar.num > 0 => sar,
+        _ => raw.default_sar,
+    };
+    // 缩放后 显示宽高比 不变
+    let native_dar = frame.width as f64 / frame.height as f64 * sample_aspect_ratio(sar);
+    video.sample_aspect_ratio = native_dar / (video.width as f64 / video.height as f64);
+    video.native_width = frame.width as usize;
+    video.native_height = frame.height as usize;
+    // 缩放过的帧 保留原始帧, 用于按原始分辨率截图
+    if video.width != video.native_width || video.height != video.native_height {
+        video.source = Some(Arc::new(NativeFrame {
+            frame: frame.clone(),
+            color_space,
+        }));
+    }
+    video
+}
+
+/// 暂停时 用当前的滤镜和色彩调节 重新生成最后显示的一帧
+pub fn redraw_paused_frame(play_ctrl: &PlayControl) {
+    let raw = match play_ctrl.last_frame() {
+        Some(raw) if raw.serial == play_ctrl.serial() => raw,
+        _ => return,
+    };
+    let tone_map = tone_map_filter(&raw.frame, play_ctrl.tone_mapping());
+    let mut graph = VideoGraph::new(
+        play_ctrl,
+        &raw.frame,
+        raw.time_base,
+        tone_map,
+        play_ctrl.video_filter(),
+        play_ctrl.color(),
+        raw.serial,
+    );
+    let mut frames = graph.filter(raw.frame.clone(), raw.time_base);
+    frames.extend(graph.flush());
+    let (frame, time_base) = match frames.pop() {
+        Some(frame) => frame,
+        None => return,
+    };
+
+    let color_space = play_ctrl.color_space();
+    let dst_size = scaled_size(&frame, play_ctrl.display_size());
+    let flags = play_ctrl.scaler().sws_flags();
+    let mut converter = match RgbaConverter::new(&frame, color_space, dst_size, flags) {
+        Some(converter) => converter,
+        None => return,
+    };
+    let rgb_frame = match converter.convert(&frame) {
+        Ok(rgb_frame) => rgb_frame,
+        Err(e) => {
+            log::error!("video sws scale_frame failed, error: {:?}", e);
+            return;
+        }
+    };
+    let mut video = build_video_frame(&frame, time_base, rgb_frame, &raw, color_space);
+    video.raw = Some(raw);
+    play_ctrl.send_state(PlayState::Video(video)).ok();
+}
+
+/// 设置解码器跳过哪些帧
+fn set_skip_frame(decode_ctx: &mut DecodeContext, discard: ffi::AVDiscard) {
+    unsafe { (*decode_ctx.dec_ctx().as_mut_ptr()).skip_frame = discard };
+}
+
+/// 顺时针旋转 degrees 度的滤镜, 只支持 90° 的倍数
+pub fn rotation_filter(degrees: f64) -> Option<&'static str> {
+    let degrees = degrees.rem_euclid(360.0);
+    if (degrees - 90.0).abs() < 1.0 {
+        Some("transpose=clock")
+    } else if (degrees - 180.0).abs() < 1.0 {
+        Some("hflip,vflip")
+    } else if (degrees - 270.0).abs() < 1.0 {
+        Some("transpose=cclock")
+    } else {
+        None
+    }
+}
+
+/// PQ (HDR10) 或 HLG 传输特性的视频
+fn is_hdr(frame: &AVFrame) -> bool {
+    matches!(
+        frame.color_trc,
+        ffi::AVColorTransferCharacteristic_AVCOL_TRC_SMPTE2084
+            | ffi::AVColorTransferCharacteristic_AVCOL_TRC_ARIB_STD_B67
+    )
+}
+
+/// HDR 转 SDR 的滤镜: 转为线性光, 映射到 BT.709 色域, 色调映射后 转回 BT.709 的 YUV
+///
+/// 需要 FFmpeg 编译时启用 libzimg (zscale 滤镜)
+fn tone_map_filter(frame: &AVFrame, tone_mapping: ToneMapping) -> Option<String> {
+    let enabled = match tone_mapping.mode {
+        ToneMapMode::Auto => is_hdr(frame),
+        ToneMapMode::On => true,
+        ToneMapMode::Off => false,
+    };
+    if !enabled {
+        return None;
+    }
+    // 没有色彩标记时 zscale 无法转换, 按 HDR10 (PQ, BT.2020) 处理
+    let mut input = String::new();
+    if frame.color_trc == ffi::AVColorTransferCharacteristic_AVCOL_TRC_UNSPECIFIED {
+        input.push_str(":tin=smpte2084");
+    }
+    if frame.colorspace == ffi::AVColorSpace_AVCOL_SPC_UNSPECIFIED {
+        input.push_str(":min=bt2020nc");
+    }
+    if frame.color_primaries == ffi::AVColorPrimaries_AVCOL_PRI_UNSPECIFIED {
+        input.push_str(":pin=bt2020");
+    }
+    Some(format!(
+        "zscale=t=linear:npl=100{},format=gbrpf32le,zscale=p=bt709,\
+         tonemap=tonemap={}:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p",
+        input,
+        tone_mapping.algorithm.name()
+    ))
+}
+
+/// 视频滤镜图, 滤镜改变, 输入帧的格式改变 或 跳转后需要重新创建
+struct VideoGraph {
+    /// 没有滤镜 或 滤镜创建失败时为 None, 帧直接输出
+    graph: Option<AVFilterGraph>,
+    /// 色调映射, 不需要时为 None
+    tone_map: Option<String>,
+    /// 滤镜图中是否包含色调映射, 色调映射不可用时为 false
+    tone_mapped: bool,
+    /// 送入或取出帧时出错
+    failed: bool,
+    filter: String,
+    color: ColorAdjust,
+    serial: usize,
+    width: i32,
+    height: i32,
+    pix_fmt: i32,
+}
+
+impl VideoGraph {
+    /// 滤镜创建失败时 帧直接输出, 并通知 ui
+    fn new(
+        play_ctrl: &PlayControl,
+        frame: &AVFrame,
+        time_base: AVRational,
+        tone_map: Option<String>,
+        filter: String,
+        color: ColorAdjust,
+        serial: usize,
+    ) -> Self {
+        let mut graph = Self::parse(frame, time_base, tone_map.as_deref(), &filter, &color);
+        let mut tone_mapped = tone_map.is_some();
+        // 色调映射的滤镜不可用时, 不使用色调映射
+        if graph.is_err() && tone_map.is_some() {
+            tone_mapped = false;
+            log::warn!("色调映射不可用, 需要 FFmpeg 支持 zscale 滤镜");
+            graph = Self::parse(frame, time_base, None, &filter, &color);
+        }
+        if let Err(e) = &graph {
+            play_ctrl
+                .send_state(PlayState::FilterError(e.to_string()))
+                .ok();
+        }
+        Self {
+            graph: graph.ok().flatten(),
+            tone_map,
+            tone_mapped,
+            failed: false,
+            filter,
+            color,
+            serial,
+            width: frame.width,
+            height: frame.height,
+            pix_fmt: frame.format,
+        }
+    }
+
+    /// 色调映射放在最前, 色彩调节放在最后, 没有滤镜时返回 None
+    fn parse(
+        frame: &AVFrame,
+        time_base: AVRational,
+        tone_map: Option<&str>,
+        filter: &str,
+        color: &ColorAdjust,
+    ) -> Result<Option<AVFilterGraph>> {
+        let mut filters: Vec<String> = vec![];
+        if let Some(tone_map) = tone_map {
+            filters.push(tone_map.to_string());
+        }
+        if !filter.trim().is_empty() {
+            filters.push(filter.to_string());
+        }
+        if !color.is_default() {
+            filters.push(color.filter());
+        }
+        let spec = filters.join(",");
+        if spec.is_empty() {
+            return Ok(None);
+        }
+
+        video_graph_parse(
+            frame.width,
+            frame.height,
+            frame.format,
+            time_base,
+            frame.sample_aspect_ratio,
+            &spec,
+        )
+        .map(Some)
+        .map_err(|e| {
+            log::error!("视频滤镜 \"{}\" 创建失败, E: {}", spec, e);
+            e
+        })
+    }
+
+    /// 色彩调节只在 使用 / 不使用 之间切换时需要重新创建
+    fn matches(
+        &self,
+        frame: &AVFrame,
+        tone_map: &Option<String>,
+        filter: &str,
+        color: &ColorAdjust,
+        serial: usize,
+    ) -> bool {
+        self.tone_map == *tone_map
+            && self.filter == filter
+            && self.color.is_default() == color.is_default()
+            && self.serial == serial
+            && !self.size_changed(frame)
+    }
+
+    /// 输入帧的大小或格式改变, 例如 自适应码流切换清晰度, 或 拼接的文件
+    fn size_changed(&self, frame: &AVFrame) -> bool {
+        self.width != frame.width || self.height != frame.height || self.pix_fmt != frame.format
+    }
+
+    /// 通过滤镜命令修改色彩调节的参数
+    fn set_color(&mut self, color: ColorAdjust) {
+        if self.color == color {
+            return;
+        }
+        self.color = color;
+        let graph = match &mut self.graph {
+            Some(graph) if !color.is_default() => graph,
+            _ => return,
+        };
+        for (target, cmd, arg) in color.commands() {
+            let c_target = CString::new(target).unwrap();
+            let c_cmd = CString::new(cmd).unwrap();
+            let c_arg = CString::new(arg.to_string()).unwrap();
+            let ret = unsafe {
+                ffi::avfilter_graph_send_command(
+                    graph.as_mut_ptr(),
+                    c_target.as_ptr(),
+                    c_cmd.as_ptr(),
+                    c_arg.as_ptr(),
+                    ptr::null_mut(),
+                    0,
+                    0,
+                )
+            };
+            if ret < 0 {
+                log::error!("滤镜命令 {} {}={} 失败, E: {}", target, cmd, arg, ret);
+            }
+        }
+    }
+
+    /// 送入一帧, 返回滤镜输出的帧 和 它们的时间基, 可能没有 也可能有多帧
+    fn filter(&mut self, frame: AVFrame, time_base: AVRational) -> Vec<(AVFrame, AVRational)> {
+        if self.graph.is_none() {
+            return vec![(frame, time_base)];
+        }
+        let frames = self.send(Some(frame.clone()));
+        // zscale 在处理帧时才检查输入的色彩, 失败时不使用色调映射重新创建
+        if self.failed && self.tone_mapped {
+            log::warn!("色调映射失败, 不使用色调映射");
+            self.tone_mapped = false;
+            self.failed = false;
+            self.graph = Self::parse(&frame, time_base, None, &self.filter, &self.color)
+                .ok()
+                .flatten();
+            return self.filter(frame, time_base);
+        }
+        frames
+    }
+
+    /// 结束输入, 取出滤镜中缓存的所有帧
+    fn flush(&mut self) -> Vec<(AVFrame, AVRational)> {
+        if self.graph.is_none() {
+            return vec![];
+        }
+        self.send(None)
+    }
+
+    /// frame 为 None 时表示输入结束
+    fn send(&mut self, frame: Option<AVFrame>) -> Vec<(AVFrame, AVRational)> {
+        let graph = match &mut self.graph {
+            Some(graph) => graph,
+            None => return vec![],
+        };
+
+        if let Err(e) = graph
+            .get_filter(cstr::cstr!("buffer@video0"))
+            .expect("get buffer@video0 failed")
+            .buffersrc_add_frame(frame, None)
+        {
+            log::error!("Error while feeding the video filtergraph, E: {}", e);
+            self.failed = true;
+            return vec![];
+        }
+
+        let mut sink = graph
+            .get_filter(cstr::cstr!("buffersink@out"))
+            .expect("get buffersink@out failed");
+        let time_base = unsafe { ffi::av_buffersink_get_time_base(sink.as_ptr()) };
+
+        let mut frames = vec![];
+        loop {
+            match sink.buffersink_get_frame(None) {
+                Ok(frame) => frames.push((frame, time_base)),
+                Err(RsmpegError::BufferSinkDrainError | RsmpegError::BufferSinkEofError) => break,
+                Err(e) => {
+                    log::error!("Get frame from video buffer sink failed, E: {}", e);
+                    self.failed = true;
+                    break;
+                }
+            }
+        }
+        frames
+    }
+}
+
+/// filter: 用逗号分隔的滤镜, 例如 "yadif,hflip"
+pub fn video_graph_parse(
+    width: i32,
+    height: i32,
+    pix_fmt: i32,
+    time_base: AVRational,
+    sample_aspect_ratio: AVRational,
+    filter: &str,
+) -> Result<AVFilterGraph> {
+    let filter_graph = AVFilterGraph::new();
+
+    // 未知的像素宽高比按 1:1 处理
+    let sar = if sample_aspect_ratio.num > 0 && sample_aspect_ratio.den > 0 {
+        sample_aspect_ratio
+    } else {
+        AVRational { num: 1, den: 1 }
+    };
+
+    let buffer_filter = format!(
+        "buffer@video0=video_size={}x{}:pix_fmt={}:time_base={}/{}:pixel_aspect={}/{} [video0_src]",
+        width, height, pix_fmt, time_base.num, time_base.den, sar.num, sar.den
+    );
+    let user_filter = format!("[video0_src] {} [video0_out]", filter);
+    let buffersink_filter = "[video0_out] buffersink@out";
+
+    let filter_spec = &CString::new(format!(
+        "{}; {}; {}",
+        buffer_filter, user_filter, buffersink_filter
+    ))?;
+
+    log::debug!("video filter_spec: {:?}", filter_spec);
+
+    filter_graph.parse_ptr(filter_spec, None, None)?;
+
+    filter_graph.config()?;
+
+    Ok(filter_graph)
+}
+
+/// YUV 转 RGB 使用的 (sws 矩阵, 是否完整范围)
+///
+/// 视频中没有记录矩阵时, 参考色域, 再根据分辨率推测: 高清视频使用 BT.709, 标清视频使用 BT.601
+fn yuv_color_space(frame: &AVFrame, color_space: ColorSpaceOverride) -> (u32, bool) {
+    let matrix = match color_space.matrix {
+        YuvMatrix::Auto => match frame.colorspace {
+            ffi::AVColorSpace_AVCOL_SPC_BT709 => ffi::SWS_CS_ITU709,
+            ffi::AVColorSpace_AVCOL_SPC_FCC => ffi::SWS_CS_FCC,
+            ffi::AVColorSpace_AVCOL_SPC_BT470BG | ffi::AVColorSpace_AVCOL_SPC_SMPTE170M => {
+                ffi::SWS_CS_ITU601
+            }
+            ffi::AVColorSpace_AVCOL_SPC_SMPTE240M => ffi::SWS_CS_SMPTE240M,
+            ffi::AVColorSpace_AVCOL_SPC_BT2020_NCL | ffi::AVColorSpace_AVCOL_SPC_BT2020_CL => {
+                ffi::SWS_CS_BT2020
+            }
+            _ => match frame.color_primaries {
+                ffi::AVColorPrimaries_AVCOL_PRI_BT709 => ffi::SWS_CS_ITU709,
+                ffi::AVColorPrimaries_AVCOL_PRI_BT2020 => ffi::SWS_CS_BT2020,
+                _ if frame.height >= 720 => ffi::SWS_CS_ITU709,
+                _ => ffi::SWS_CS_ITU601,
+            },
+        },
+        YuvMatrix::Bt601 => ffi::SWS_CS_ITU601,
+        YuvMatrix::Bt709 => ffi::SWS_CS_ITU709,
+        YuvMatrix::Smpte240m => ffi::SWS_CS_SMPTE240M,
+        YuvMatrix::Bt2020 => ffi::SWS_CS_BT2020,
+    };
+
+    // yuvj 格式 已废弃, 但还有解码器输出, 它们总是完整范围
+    let full_range = match color_space.range {
+        YuvRange::Auto => {
+            frame.color_range == ffi::AVColorRange_AVCOL_RANGE_JPEG
+                || matches!(
+                    frame.format,
+                    ffi::AVPixelFormat_AV_PIX_FMT_YUVJ420P
+                        | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ422P
+                        | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ444P
+                        | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ440P
+                        | ffi::AVPixelFormat_AV_PIX_FMT_YUVJ411P
+                )
+        }
+        YuvRange::Limited => false,
+        YuvRange::Full => true,
+    };
+    (matrix, full_range)
+}
+
+/// 转换后的大小: 显示区域比原始帧小时缩小到显示大小, 否则使用原始大小, 放大交给 GPU
+fn scaled_size(frame: &AVFrame, display_size: Option<(i32, i32)>) -> (i32, i32) {
+    match display_size {
+        Some((width, height))
+            if width > 0
+                && height > 0
+                && (width as i64 * height as i64) < (frame.width as i64 * frame.height as i64) =>
+        {
+            (width, height)
+        }
+        _ => (frame.width, frame.height),
+    }
+}
+
+/// 把视频帧转换为 RGBA 格式, 帧的大小, 格式, 色彩空间 或 输出大小改变时需要重新创建
+struct RgbaConverter {
+    sws: SwsContext,
+    rgb_frame: AVFrame,
+    width: i32,
+    height: i32,
+    pix_fmt: i32,
+    /// (sws 矩阵, 是否完整范围)
+    yuv_color_space: (u32, bool),
+    /// 输出的宽高
+    dst_size: (i32, i32),
+    /// 缩放算法
+    flags: u32,
+}
+
+impl RgbaConverter {
+    fn new(
+        frame: &AVFrame,
+        color_space: ColorSpaceOverride,
+        dst_size: (i32, i32),
+        flags: u32,
+    ) -> Option<Self> {
+        let (width, height, pix_fmt) = (frame.width, frame.height, frame.format);
+        let (dst_width, dst_height) = dst_size;
+
+        let mut sws = SwsContext::get_context(
+            width,
+            height,
+            pix_fmt,
+            dst_width,
+            dst_height,
+            ffi::AVPixelFormat_AV_PIX_FMT_RGBA,
+            flags,
+        )
+        .or_else(|| {
+            log::error!("Failed to create a swscale context.");
+            None
+        })?;
+
+        // 默认的系数是 BT.601 有限范围, 需要按照视频的色彩空间设置, 输出的 RGB 是完整范围
+        let yuv_color_space = yuv_color_space(frame, color_space);
+        let (matrix, full_range) = yuv_color_space;
+        log::info!(
+            "YUV 转 RGB: 矩阵 {}, 完整范围 {}, 色域 {}",
+            matrix,
+            full_range,
+            frame.color_primaries
+        );
+        unsafe {
+            ffi::sws_setColorspaceDetails(
+                sws.as_mut_ptr(),
+                ffi::sws_getCoefficients(matrix as i32),
+                full_range as i32,
+                ffi::sws_getCoefficients(ffi::SWS_CS_DEFAULT as i32),
+                1,
+                0,
+                1 << 16,
+                1 << 16,
+            );
+        }
+
+        // 用于把 接收到的帧数据 转换成 特定格式的帧数据
+        let mut rgb_frame = AVFrame::new();
+        rgb_frame.set_format(ffi::AVPixelFormat_AV_PIX_FMT_RGBA);
+        rgb_frame.set_width(dst_width);
+        rgb_frame.set_height(dst_height);
+        rgb_frame
+            .alloc_buffer()
+            .map_err(|e| log::error!("frame alloc_buffer failed, error: {:?}", e))
+            .ok()?;
+
+        Some(Self {
+            sws,
+            rgb_frame,
+            width,
+            height,
+            pix_fmt,
+            yuv_color_space,
+            dst_size,
+            flags,
+        })
+    }
+
+    fn matches(
+        &self,
+        frame: &AVFrame,
+        color_space: ColorSpaceOverride,
+        dst_size: (i32, i32),
+        flags: u32,
+    ) -> bool {
+        self.width == frame.width
+            && self.height == frame.height
+            && self.pix_fmt == frame.format
+            && self.yuv_color_space == yuv_color_space(frame, color_space)
+            && self.dst_size == dst_size
+            && self.flags == flags
+    }
+
+    fn convert(&mut self, frame: &AVFrame) -> Result<&AVFrame> {
+        self.sws
+            .scale_frame(frame, 0, frame.height, &mut self.rgb_frame)
+            .map_err(|e| PlayerError::Error(e.to_string()))?;
+        Ok(&self.rgb_frame)
+    }
+}
+
+/// 未知的像素宽高比按 1:1 处理
+fn sample_aspect_ratio(sar: AVRational) -> f64 {
+    if sar.num > 0 && sar.den > 0 {
+        av_q2d(sar)
+    } else {
+        1.0
+    }
+}
+
 pub fn video_play_thread(
     play_ctrl: super::PlayControl,
     video_frame_queue: Receiver<VideoFrame>,
 ) -> Result<()> {
     let mut empty_count = 0;
+    // 连续丢弃的帧数
+    let mut drop_count = 0;
 
     loop {
         if play_ctrl.abort_request() {
@@ -115,7 +732,19 @@ pub fn video_play_thread(
         }
 
         if let Ok(frame) = video_frame_queue.try_recv() {
-            play_ctrl.play_video(frame)?;
+            // 跳转之前解码的帧, 直接丢弃
+            if frame.serial == play_ctrl.serial() {
+                // 视频落后太多时丢弃, 追上音频
+                if play_ctrl.video_too_late(frame.pts) && drop_count < VIDEO_MAX_CONSECUTIVE_DROPS {
+                    drop_count += 1;
+                    play_ctrl.frame_stats().add_dropped();
+                } else {
+                    drop_count = 0;
+                    play_ctrl.set_last_frame(frame.raw.clone());
+                    play_ctrl.play_video(frame)?;
+                    play_ctrl.frame_stats().add_presented();
+                }
+            }
             empty_count = 0;
             continue;
         }
@@ -131,6 +760,55 @@ pub fn video_play_thread(
     Ok(())
 }
 
+/// 滤镜之前的解码帧, 暂停时修改色彩调节后 用它重新生成画面
+pub struct RawFrame {
+    frame: AVFrame,
+    time_base: AVRational,
+    /// 帧中没有像素宽高比时使用的 解码器中的像素宽高比
+    default_sar: AVRational,
+    duration: f64,
+    serial: usize,
+}
+
+// 只读访问 引用计数的帧数据
+unsafe impl Send for RawFrame {}
+unsafe impl Sync for RawFrame {}
+
+/// 缩放前的原始帧, 截图时再转换为 RGBA
+pub struct NativeFrame {
+    frame: AVFrame,
+    color_space: ColorSpaceOverride,
+}
+
+// 只读访问 引用计数的帧数据
+unsafe impl Send for NativeFrame {}
+unsafe impl Sync for NativeFrame {}
+
+impl NativeFrame {
+    /// 按原始分辨率转换为 RGBA 数据
+    fn to_rgba(&self) -> Result<Vec<u8>> {
+        let frame = &self.frame;
+        let mut converter = RgbaConverter::new(
+            frame,
+            self.color_space,
+            (frame.width, frame.height),
+            ffi::SWS_BICUBIC,
+        )
+        .ok_or_else(|| PlayerError::Error("创建 swscale 失败".to_string()))?;
+        let rgb_frame = converter.convert(frame)?;
+        let video = VideoFrame::new(
+            rgb_frame.data[0] as *const u8,
+            rgb_frame.width as usize,
+            rgb_frame.height as usize,
+            rgb_frame.linesize[0] as usize,
+            0.0,
+            0.0,
+            0,
+        );
+        Ok(video.data)
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct VideoFrame {
     pub data: Vec<u8>,
@@ -138,6 +816,17 @@ pub struct VideoFrame {
     pub height: usize,
     pub pts: f64,
     pub duration: f64,
+    /// 跳转序号
+    pub serial: usize,
+    /// 像素宽高比(SAR)
+    pub sample_aspect_ratio: f64,
+    /// 缩放前的宽高
+    pub native_width: usize,
+    pub native_height: usize,
+    /// 缩放前的原始帧, 没有缩放时为 None
+    pub source: Option<Arc<NativeFrame>>,
+    /// 滤镜之前的解码帧
+    pub raw: Option<Arc<RawFrame>>,
 }
 
 impl VideoFrame {
@@ -148,6 +837,7 @@ impl VideoFrame {
         line_size: usize,
         pts: f64,
         duration: f64,
+        serial: usize,
     ) -> Self {
         let raw_data = unsafe { std::slice::from_raw_parts(raw_data, height * line_size) };
         let mut data: Vec<u8> = vec![0; width * height * 4];
@@ -167,7 +857,46 @@ impl VideoFrame {
             height,
             pts,
             duration,
+            serial,
+            sample_aspect_ratio: 1.0,
+            native_width: width,
+            native_height: height,
+            source: None,
+            raw: None,
+        }
+    }
+
+    /// 显示宽高比(DAR) = 宽 / 高 * 像素宽高比
+    pub fn display_aspect_ratio(&self) -> f64 {
+        if self.width == 0 || self.height == 0 {
+            return 1.0;
         }
+        let sar = if self.sample_aspect_ratio > 0.0 {
+            self.sample_aspect_ratio
+        } else {
+            1.0
+        };
+        self.width as f64 / self.height as f64 * sar
+    }
+
+    /// 保存为 png 图片 (原始分辨率)
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        let (data, width, height) = match &self.source {
+            Some(source) => (
+                Cow::Owned(source.to_rgba()?),
+                self.native_width,
+                self.native_height,
+            ),
+            None => (Cow::Borrowed(&self.data), self.width, self.height),
+        };
+        image::save_buffer(
+            path,
+            &data,
+            width as u32,
+            height as u32,
+            image::ColorType::Rgba8,
+        )
+        .map_err(|e| PlayerError::Error(e.to_string()))
     }
 }
 
@@ -0,0 +1,290 @@
+use std::path::PathBuf;
+
+use bevy::window::WindowMode;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::toml_file,
+    defines::{APP_DIR_NAME, AUDIO_FRAME_QUEUE_SIZE, PACKET_QUEUE_MAX_MEM, VIDEO_FRAME_QUEUE_SIZE},
+    resources::{
+        display::{Rotation, VisualizerStyle},
+        keymap::Keymap,
+        playlist::RepeatMode,
+        theme::DEFAULT_THEME,
+    },
+};
+
+use super::{
+    history::ResumeMode,
+    player::{
+        video::rotation_filter, ColorSpaceOverride, Downmix, Equalizer, Loudness, PlayOptions,
+        ScalerQuality, ToneMapping,
+    },
+};
+
+/// 启动时的窗口模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum StartupWindow {
+    Windowed,
+    #[default]
+    Maximized,
+    Fullscreen,
+}
+
+impl StartupWindow {
+    pub const ALL: [StartupWindow; 3] = [Self::Windowed, Self::Maximized, Self::Fullscreen];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Windowed => "窗口",
+            Self::Maximized => "最大化",
+            Self::Fullscreen => "全屏",
+        }
+    }
+
+    /// (是否最大化, 窗口模式)
+    pub fn window_state(&self) -> (bool, WindowMode) {
+        match self {
+            Self::Windowed => (false, WindowMode::Windowed),
+            Self::Maximized => (true, WindowMode::Windowed),
+            Self::Fullscreen => (true, WindowMode::Fullscreen),
+        }
+    }
+}
+
+/// 播放引擎的缓冲区大小, 下次打开文件时生效
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BufferSettings {
+    /// 解码后的音频帧队列长度
+    pub audio_frame_queue: usize,
+    /// 解码后的视频帧队列长度
+    pub video_frame_queue: usize,
+    /// 每个流的 packet 队列最多占用的内存(字节)
+    pub packet_queue_mem: i32,
+}
+
+impl Default for BufferSettings {
+    fn default() -> Self {
+        Self {
+            audio_frame_queue: AUDIO_FRAME_QUEUE_SIZE,
+            video_frame_queue: VIDEO_FRAME_QUEUE_SIZE,
+            packet_queue_mem: PACKET_QUEUE_MAX_MEM,
+        }
+    }
+}
+
+/// 均衡器的预设
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqualizerPreset {
+    pub name: String,
+    pub equalizer: Equalizer,
+}
+
+impl EqualizerPreset {
+    fn new(name: &str, gains: [f32; 10]) -> Self {
+        Self {
+            name: name.to_string(),
+            equalizer: Equalizer::new(gains),
+        }
+    }
+
+    /// 内置的预设
+    pub fn builtin() -> Vec<EqualizerPreset> {
+        vec![
+            Self::new("平直", [0.0; 10]),
+            Self::new("摇滚", [5.0, 4.0, 3.0, 1.0, -1.0, -1.0, 1.0, 3.0, 4.0, 5.0]),
+            Self::new(
+                "人声",
+                [-3.0, -2.0, -1.0, 1.0, 3.0, 4.0, 4.0, 2.0, 0.0, -1.0],
+            ),
+            Self::new("重低音", [7.0, 6.0, 5.0, 3.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        ]
+    }
+}
+
+/// 均衡器, 修改后立即应用到正在播放的音频
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EqualizerSettings {
+    pub enabled: bool,
+    pub equalizer: Equalizer,
+    /// 用户保存的预设
+    pub presets: Vec<EqualizerPreset>,
+}
+
+impl EqualizerSettings {
+    /// 实际使用的均衡器, 关闭时为平直
+    pub fn active(&self) -> Equalizer {
+        if self.enabled {
+            self.equalizer
+        } else {
+            Equalizer::default()
+        }
+    }
+}
+
+/// 视频滤镜, 修改后立即应用到正在播放的视频
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct VideoFilterSettings {
+    /// 去隔行, 只处理标记为隔行的帧
+    pub deinterlace: bool,
+    /// 降噪
+    pub denoise: bool,
+    /// 锐化
+    pub sharpen: bool,
+    /// 水平翻转
+    pub hflip: bool,
+    /// 垂直翻转
+    pub vflip: bool,
+    pub rotation: Rotation,
+    /// 追加在预设之后的 libavfilter 滤镜, 例如 "eq=gamma=1.2"
+    pub custom: String,
+}
+
+impl VideoFilterSettings {
+    /// 用逗号连接的滤镜, 没有滤镜时为空
+    pub fn filter(&self) -> String {
+        let presets = [
+            (self.deinterlace, "yadif=deint=interlaced"),
+            (self.denoise, "hqdn3d"),
+            (self.sharpen, "unsharp=5:5:1.0"),
+            (self.hflip, "hflip"),
+            (self.vflip, "vflip"),
+        ];
+        let mut filters: Vec<&str> = presets
+            .into_iter()
+            .filter_map(|(enabled, filter)| enabled.then_some(filter))
+            .collect();
+        filters.extend(rotation_filter(self.rotation.degrees()));
+        let custom = self.custom.trim();
+        if !custom.is_empty() {
+            filters.push(custom);
+        }
+        filters.join(",")
+    }
+}
+
+/// 用户设置, 保存在 $XDG_CONFIG_HOME/small-player/config.toml
+///
+/// 配置文件中缺少的项使用默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// 启动时的音量
+    pub volume: f32,
+    pub startup_window: StartupWindow,
+    /// 界面缩放
+    pub scale_factor: f64,
+    /// 主题的名字
+    pub theme: String,
+    pub repeat: RepeatMode,
+    pub resume_mode: ResumeMode,
+    /// 上次打开文件的目录
+    pub open_dir: Option<PathBuf>,
+    /// 优先选择的音轨语言, 逗号分隔, 例如 "chi,eng"
+    pub audio_languages: String,
+    /// 优先选择的字幕语言, 逗号分隔 (目前还不支持字幕, 仅保存)
+    pub subtitle_languages: String,
+    pub buffer: BufferSettings,
+    pub video_filter: VideoFilterSettings,
+    /// 视频缩放到显示大小时使用的算法
+    pub scaler: ScalerQuality,
+    /// HDR 视频的色调映射
+    pub tone_mapping: ToneMapping,
+    /// 没有视频时 音频可视化的样式
+    pub visualizer: VisualizerStyle,
+    /// 音频设备的名字, None 为默认设备
+    pub audio_device: Option<String>,
+    pub equalizer: EqualizerSettings,
+    /// 响度均衡
+    pub loudness: Loudness,
+    /// 声道混合
+    pub downmix: Downmix,
+    /// 调试用, 覆盖视频中记录的色彩空间
+    pub color_space: ColorSpaceOverride,
+    /// 快捷键
+    pub keymap: Keymap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            startup_window: StartupWindow::default(),
+            scale_factor: 1.25,
+            theme: DEFAULT_THEME.to_string(),
+            repeat: RepeatMode::default(),
+            resume_mode: ResumeMode::default(),
+            open_dir: None,
+            audio_languages: String::new(),
+            subtitle_languages: String::new(),
+            buffer: BufferSettings::default(),
+            video_filter: VideoFilterSettings::default(),
+            scaler: ScalerQuality::default(),
+            tone_mapping: ToneMapping::default(),
+            visualizer: VisualizerStyle::default(),
+            audio_device: None,
+            equalizer: EqualizerSettings::default(),
+            loudness: Loudness::default(),
+            downmix: Downmix::default(),
+            color_space: ColorSpaceOverride::default(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// 从配置文件加载, 文件不存在时使用默认值
+    pub fn load() -> Self {
+        config_path()
+            .as_deref()
+            .and_then(toml_file::load)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = config_path() {
+            toml_file::save(&path, self);
+        }
+    }
+
+    /// 打开文件时使用的选项
+    pub fn play_options(&self) -> PlayOptions {
+        PlayOptions {
+            audio_languages: split_languages(&self.audio_languages),
+            audio_frame_queue: self.buffer.audio_frame_queue.max(1),
+            video_frame_queue: self.buffer.video_frame_queue.max(1),
+            packet_queue_mem: self.buffer.packet_queue_mem.max(1024 * 1024),
+            audio_track: None,
+            speed: 1.0,
+            video_filter: self.video_filter.filter(),
+            rotation: None,
+            color: Default::default(),
+            color_space: self.color_space,
+            tone_mapping: self.tone_mapping,
+            scaler: self.scaler,
+            display_size: None,
+            audio_device: self.audio_device.clone(),
+            equalizer: self.equalizer.active(),
+            loudness: self.loudness,
+            downmix: self.downmix.clone(),
+            audio_delay: 0.0,
+            volume: self.volume,
+            mute: false,
+        }
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME).join("config.toml"))
+}
+
+fn split_languages(languages: &str) -> Vec<String> {
+    languages
+        .split(',')
+        .map(|lang| lang.trim().to_lowercase())
+        .filter(|lang| !lang.is_empty())
+        .collect()
+}
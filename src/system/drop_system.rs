@@ -0,0 +1,73 @@
+use bevy::prelude::*;
+use bevy_egui::{egui::pos2, EguiSettings};
+
+use crate::{
+    common::media::collect_media_files, resources::event::PlayerEvent,
+    services::player::formats::MediaFormats, ui::ui_state::UiState,
+};
+
+/// 处理拖拽到窗口中的文件和文件夹
+///
+/// 文件追加到播放列表, 文件夹会递归扫描其中的音视频文件;
+/// 如果拖放到了视频区域, 则立即播放拖入的第一个文件
+pub fn update_drop(
+    mut drop_evt: EventReader<FileDragAndDrop>,
+    mut ui_state: ResMut<UiState>,
+    mut player_evt: EventWriter<PlayerEvent>,
+    windows: Res<Windows>,
+    egui_settings: Res<EguiSettings>,
+) {
+    // 同一次拖放的多个文件, 会在同一帧里产生多个 DroppedFile 事件
+    let mut first_dropped = None;
+    let mut media_formats = None;
+
+    for event in drop_evt.iter() {
+        match event {
+            FileDragAndDrop::HoveredFile { .. } => {
+                ui_state.drop_hovering = true;
+            }
+            FileDragAndDrop::HoveredFileCancelled { .. } => {
+                ui_state.drop_hovering = false;
+            }
+            FileDragAndDrop::DroppedFile { path_buf, .. } => {
+                ui_state.drop_hovering = false;
+
+                let media_formats = media_formats.get_or_insert_with(MediaFormats::from_demuxers);
+                let files = collect_media_files(path_buf, &media_formats.media_extensions());
+                log::info!("拖入 {:?}, 文件数: {}", path_buf, files.len());
+
+                if !files.is_empty() && first_dropped.is_none() {
+                    first_dropped = Some(ui_state.playlist.len());
+                }
+                for file in files {
+                    ui_state.playlist.push(file.to_string_lossy().into_owned());
+                }
+            }
+        }
+    }
+
+    let first_dropped = match first_dropped {
+        Some(idx) => idx,
+        None => return,
+    };
+
+    // bevy 的光标坐标以窗口左下角为原点, 需要转换成 egui 的坐标
+    let on_video = windows
+        .get_primary()
+        .and_then(|window| {
+            window.cursor_position().map(|cursor| {
+                let scale_factor = egui_settings.scale_factor as f32;
+                pos2(
+                    cursor.x / scale_factor,
+                    (window.height() - cursor.y) / scale_factor,
+                )
+            })
+        })
+        .map(|pos| ui_state.video_rect.contains(pos))
+        .unwrap_or(false);
+
+    if on_video {
+        ui_state.playlist.select(first_dropped);
+        player_evt.send(PlayerEvent::Play);
+    }
+}
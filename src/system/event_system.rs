@@ -1,13 +1,23 @@
+use std::path::Path;
+
 use bevy::{
     app::AppExit,
     diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin},
     prelude::*,
-    window::{WindowId, WindowMode},
+    window::{WindowCloseRequested, WindowId, WindowMode},
     winit::WinitWindows,
 };
 
 use crate::{
-    resources::event::PlayerEvent, services::player::player::Player, ui::ui_state::UiState,
+    common::{media::collect_media_files, utils::format_duration},
+    defines::{APP_DIR_NAME, AUDIO_DELAY_MAX},
+    resources::{display::Rotation, event::PlayerEvent, playlist::PlayItemState},
+    services::{
+        history::{ResumeMode, WatchHistory},
+        player::{formats::MediaFormats, player::Player, MediaInfo, PlayOptions},
+        settings::Settings,
+    },
+    ui::{notice_overlay::Notice, ui_state::UiState},
 };
 
 use super::GameState;
@@ -19,6 +29,8 @@ pub fn update_event(
     mut game_state: ResMut<State<GameState>>,
     mut player: ResMut<Player>,
     mut player_evt: EventReader<PlayerEvent>,
+    mut history: ResMut<WatchHistory>,
+    mut settings: ResMut<Settings>,
     mut windows: ResMut<Windows>,
     mut exit: EventWriter<AppExit>,
 ) {
@@ -34,6 +46,7 @@ pub fn update_event(
                 窗口控制
             */
             PlayerEvent::Exit => {
+                history.save();
                 exit.send(AppExit);
             }
             PlayerEvent::Fullscreen => {
@@ -71,14 +84,36 @@ pub fn update_event(
                 播放控制
             */
             PlayerEvent::OpenFile => {
-                if let Some(files) = rfd::FileDialog::new()
-                    .add_filter("video", &["mp4"])
-                    .pick_files()
-                {
+                let formats = MediaFormats::from_demuxers();
+                let groups = [
+                    ("音视频", formats.media_extensions()),
+                    ("视频", as_strs(&formats.video)),
+                    ("音频", as_strs(&formats.audio)),
+                    ("播放列表", as_strs(&formats.playlist)),
+                    ("字幕", as_strs(&formats.subtitle)),
+                    ("所有文件", vec!["*"]),
+                ];
+                let mut dialog = open_dialog(&settings);
+                for (name, extensions) in groups.iter() {
+                    if !extensions.is_empty() {
+                        dialog = dialog.add_filter(name, extensions);
+                    }
+                }
+                if let Some(files) = dialog.pick_files() {
+                    if let Some(dir) = files.first().and_then(|file| file.parent()) {
+                        settings.open_dir = Some(dir.to_path_buf());
+                    }
                     for file in files {
-                        ui_state
-                            .play_list
-                            .push(file.into_os_string().into_string().unwrap());
+                        ui_state.playlist.push(file.to_string_lossy().into_owned());
+                    }
+                }
+            }
+            PlayerEvent::OpenFolder => {
+                if let Some(folder) = open_dialog(&settings).pick_folder() {
+                    settings.open_dir = Some(folder.clone());
+                    let formats = MediaFormats::from_demuxers();
+                    for file in collect_media_files(&folder, &formats.media_extensions()) {
+                        ui_state.playlist.push(file.to_string_lossy().into_owned());
                     }
                 }
             }
@@ -91,6 +126,7 @@ pub fn update_event(
                 continue;
             }
             PlayerEvent::Mute(mute) => {
+                ui_state.mute = *mute;
                 player.set_mute(*mute);
                 continue;
             }
@@ -98,33 +134,251 @@ pub fn update_event(
                 player.set_volume(*volume);
                 continue;
             }
+            PlayerEvent::Seek(offset) => {
+                if let Some(position) = player.position() {
+                    let mut position = (position + offset).max(0.0);
+                    if let Some(duration) =
+                        ui_state.playlist.current().and_then(|item| item.duration)
+                    {
+                        position = position.min(duration);
+                    }
+                    player.seek(position);
+                }
+                continue;
+            }
+            PlayerEvent::Screenshot => {
+                save_screenshot(&ui_state, player.position().unwrap_or_default());
+                continue;
+            }
+            PlayerEvent::Speed(speed) => {
+                ui_state.speed = *speed;
+                player.set_speed(*speed);
+                continue;
+            }
+            PlayerEvent::AudioDevice(name) => {
+                if settings.audio_device == *name {
+                    // 设置没有改变时 设置系统不会切换, 例如 设备断开后重新选择同一个设备
+                    ui_state.audio_device = name.clone();
+                    player.set_audio_device(name.clone());
+                } else {
+                    // 保存到设置中, 由设置系统切换设备
+                    settings.audio_device = name.clone();
+                }
+                continue;
+            }
+            PlayerEvent::DisplaySize(width, height) => {
+                player.set_display_size(*width, *height);
+                continue;
+            }
+            PlayerEvent::Rotation(rotation) => {
+                ui_state.file_settings.rotation = *rotation;
+                if let Some(item) = ui_state.playlist.current() {
+                    history.set_file_settings(&item.path, ui_state.file_settings.clone());
+                }
+                player.set_rotation(ui_state.rotation().degrees());
+                continue;
+            }
+            PlayerEvent::Color(color) => {
+                ui_state.file_settings.color = *color;
+                if let Some(item) = ui_state.playlist.current() {
+                    history.set_file_settings(&item.path, ui_state.file_settings.clone());
+                }
+                player.set_color(*color);
+                continue;
+            }
+            PlayerEvent::AudioDelay(delay) => {
+                let delay = (delay * 100.0).round() / 100.0;
+                let delay = delay.clamp(-AUDIO_DELAY_MAX, AUDIO_DELAY_MAX);
+                ui_state.file_settings.audio_delay = delay;
+                if let Some(item) = ui_state.playlist.current() {
+                    history.set_file_settings(&item.path, ui_state.file_settings.clone());
+                }
+                ui_state.notice = Some(Notice::new(format!("音频延迟: {:+.2}s", delay)));
+                player.set_audio_delay(delay);
+                continue;
+            }
+            PlayerEvent::AudioTrack(stream_idx) => {
+                switch_audio_track(&mut ui_state, &mut player, &settings, *stream_idx);
+                continue;
+            }
+            PlayerEvent::Visualizer(style) => {
+                ui_state.visualizer.style = *style;
+                settings.visualizer = *style;
+                continue;
+            }
+            PlayerEvent::Repeat(repeat) => {
+                ui_state.playlist.set_repeat(*repeat);
+                settings.repeat = *repeat;
+                continue;
+            }
+            PlayerEvent::Shuffle(shuffle) => {
+                ui_state.playlist.set_shuffle(*shuffle);
+                continue;
+            }
             _ => {}
         }
 
-        // 根据循环模式, 和 当前选择文件 或者 未选择文件 的不同情况, 确定要播放的文件
-        let offset = match event {
-            PlayerEvent::Previous => -1,
-            PlayerEvent::Play => 0,
-            PlayerEvent::Next => 1,
+        // 根据循环模式, 随机播放 和 播放历史, 确定要播放的文件
+        let file = match event {
+            PlayerEvent::Previous => ui_state.playlist.previous(),
+            PlayerEvent::Play | PlayerEvent::Resume(_) => ui_state.playlist.play(),
+            PlayerEvent::Next => ui_state.playlist.next(),
+            PlayerEvent::AutoNext => ui_state.playlist.auto_next(),
             _ => continue,
         };
+        ui_state.resume_prompt = None;
 
         // 播放文件
-        if let Some(file) = ui_state.current_filename(offset) {
-            match player.play(file.clone()) {
-                Ok(_) => {
-                    player.set_volume(ui_state.volume);
+        if let Some(file) = file {
+            // 根据观看记录, 确定开始播放的位置
+            let resume_position = history.resume_position(&file);
+            let start = match (event, settings.resume_mode, resume_position) {
+                (PlayerEvent::Resume(true), _, Some(position)) => position,
+                (PlayerEvent::Resume(_), _, _) => 0.0,
+                (_, ResumeMode::Always, Some(position)) => position,
+                (_, ResumeMode::Ask, Some(position)) => {
+                    // 询问期间停止之前的文件, 否则它会在新的条目下继续播放
+                    if ui_state.playing {
+                        game_state.set(GameState::Terminal).ok();
+                    }
+                    ui_state.resume_prompt = Some(position);
+                    continue;
+                }
+                _ => 0.0,
+            };
 
-                    log::info!("开始播放 {}", file);
+            ui_state.file_settings = history.file_settings(&file);
+            let options = play_options(&ui_state, &settings);
+            match player.play(file.clone(), start, &options) {
+                Ok(media_info) => {
+                    log::info!("开始播放 {}, 位置: {}s", file, start);
 
+                    restore_play_state(&mut ui_state, &media_info);
+                    ui_state.playlist.set_current_state(PlayItemState::Playing);
+                    if let Some(item) = ui_state.playlist.current_mut() {
+                        item.duration = media_info.duration;
+                    }
                     ui_state.enter_playing();
                     game_state.set(GameState::Playing).ok();
                     continue;
                 }
                 Err(e) => {
                     log::info!("播放失败, E: {}", e.to_string());
+                    ui_state.playlist.set_current_state(PlayItemState::Failed);
                 }
             }
         }
+
+        // 自动播放时, 没有下一个文件了, 则停止播放
+        if let PlayerEvent::AutoNext = event {
+            game_state.set(GameState::Terminal).ok();
+        }
     }
 }
+
+/// 退出时保存观看记录和设置, 通过系统关闭窗口时, 不会收到 PlayerEvent::Exit
+pub fn save_on_exit(
+    mut close_evt: EventReader<WindowCloseRequested>,
+    mut exit_evt: EventReader<AppExit>,
+    mut history: ResMut<WatchHistory>,
+    settings: Res<Settings>,
+) {
+    let closing = close_evt.iter().count() > 0;
+    let exiting = exit_evt.iter().count() > 0;
+    if closing || exiting {
+        history.save();
+        settings.save();
+    }
+}
+
+/// 从当前位置重新打开文件, 播放指定的音轨
+fn switch_audio_track(
+    ui_state: &mut UiState,
+    player: &mut Player,
+    settings: &Settings,
+    stream_idx: usize,
+) {
+    let (file, position) = match (ui_state.playlist.current(), player.position()) {
+        (Some(item), Some(position)) => (item.path.clone(), position),
+        _ => return,
+    };
+    let options = PlayOptions {
+        audio_track: Some(stream_idx),
+        ..play_options(ui_state, settings)
+    };
+    match player.play(file, position, &options) {
+        Ok(media_info) => {
+            log::info!("切换到音轨 {}, 位置: {}s", stream_idx, position);
+            restore_play_state(ui_state, &media_info);
+            if ui_state.pause {
+                player.set_pause(true);
+            }
+        }
+        Err(e) => log::error!("切换音轨失败, E: {}", e.to_string()),
+    }
+}
+
+/// 打开文件时的选项: 用户设置, 加上当前的播放速度 和 文件的单独设置
+fn play_options(ui_state: &UiState, settings: &Settings) -> PlayOptions {
+    PlayOptions {
+        speed: ui_state.speed,
+        rotation: ui_state.file_settings.rotation.map(|r| r.degrees()),
+        color: ui_state.file_settings.color,
+        audio_delay: ui_state.file_settings.audio_delay,
+        volume: ui_state.volume,
+        mute: ui_state.mute,
+        display_size: ui_state.display_size,
+        ..settings.play_options()
+    }
+}
+
+/// 新打开的文件沿用界面上的状态, 音量和静音已经通过 play_options 设置
+fn restore_play_state(ui_state: &mut UiState, media_info: &MediaInfo) {
+    ui_state.audio_tracks = media_info.audio_tracks.clone();
+    ui_state.audio_track = media_info.audio_track;
+    ui_state.media_rotation = Rotation::from_degrees(media_info.rotation);
+    ui_state.has_video = media_info.has_video;
+}
+
+/// 从上次打开文件的目录开始选择
+fn open_dialog(settings: &Settings) -> rfd::FileDialog {
+    let dialog = rfd::FileDialog::new();
+    match &settings.open_dir {
+        Some(dir) if dir.is_dir() => dialog.set_directory(dir),
+        _ => dialog,
+    }
+}
+
+/// 截图保存在 图片目录/small-player 中, 文件名是 视频名_播放位置.png
+fn save_screenshot(ui_state: &UiState, position: f64) {
+    let (video, item) = match (&ui_state.video, ui_state.playlist.current()) {
+        (Some(video), Some(item)) => (video, item),
+        _ => return,
+    };
+    let dir = match dirs::picture_dir().or_else(dirs::home_dir) {
+        Some(dir) => dir.join(APP_DIR_NAME),
+        None => return,
+    };
+    let stem = Path::new(&item.path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| item.title.clone());
+    let name = format!(
+        "{}_{}.png",
+        stem,
+        format_duration(position).replace(':', "-")
+    );
+    let path = dir.join(name);
+
+    let result = std::fs::create_dir_all(&dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| video.save_png(&path).map_err(|e| e.to_string()));
+    match result {
+        Ok(_) => log::info!("截图已保存: {:?}", path),
+        Err(e) => log::error!("保存截图 {:?} 失败, E: {}", path, e),
+    }
+}
+
+fn as_strs(extensions: &[String]) -> Vec<&str> {
+    extensions.iter().map(|ext| ext.as_str()).collect()
+}
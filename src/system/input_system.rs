@@ -0,0 +1,106 @@
+use bevy::{prelude::*, window::WindowMode};
+use bevy_egui::EguiContext;
+
+use crate::{
+    defines::{AUDIO_DELAY_STEP, SEEK_STEP, VOLUME_MAX, VOLUME_STEP, ZOOM_STEP},
+    resources::{
+        event::PlayerEvent,
+        keymap::{Action, KeyBinding},
+    },
+    services::settings::Settings,
+    ui::{notice_overlay::Notice, ui_state::UiState},
+};
+
+/// 根据快捷键设置, 把按键转换成 PlayerEvent
+///
+/// 设置窗口正在录制快捷键时, 按下的键会绑定到对应的操作上
+pub fn update_keyboard(
+    keys: Res<Input<KeyCode>>,
+    mut egui_ctx: ResMut<EguiContext>,
+    mut ui_state: ResMut<UiState>,
+    mut settings: ResMut<Settings>,
+    mut player_evt: EventWriter<PlayerEvent>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::LControl, KeyCode::RControl]);
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+    let alt = keys.any_pressed([KeyCode::LAlt, KeyCode::RAlt]);
+
+    for key in keys.get_just_pressed() {
+        if KeyBinding::is_modifier(*key) {
+            continue;
+        }
+        let binding = KeyBinding {
+            key: *key,
+            ctrl,
+            shift,
+            alt,
+        };
+
+        // 录制快捷键, Esc 取消录制, 不能保存的按键继续等待
+        if let Some(action) = ui_state.setting_window.recording() {
+            if *key != KeyCode::Escape && !KeyBinding::is_supported(*key) {
+                ui_state.notice = Some(Notice::new(format!("不支持的按键: {:?}", key)));
+                continue;
+            }
+            if *key != KeyCode::Escape {
+                settings.keymap.bind(action, binding);
+            }
+            ui_state.setting_window.stop_recording();
+            continue;
+        }
+
+        // 正在输入文字
+        if egui_ctx.ctx_mut().wants_keyboard_input() {
+            continue;
+        }
+
+        let action = match settings.keymap.action(&binding) {
+            Some(action) => action,
+            None => continue,
+        };
+        match action {
+            Action::TogglePause => {
+                if ui_state.playing {
+                    player_evt.send(PlayerEvent::Pause(!ui_state.pause));
+                } else {
+                    player_evt.send(PlayerEvent::Play);
+                }
+            }
+            Action::SeekForward => player_evt.send(PlayerEvent::Seek(SEEK_STEP)),
+            Action::SeekBackward => player_evt.send(PlayerEvent::Seek(-SEEK_STEP)),
+            Action::VolumeUp | Action::VolumeDown => {
+                let step = if action == Action::VolumeUp {
+                    VOLUME_STEP
+                } else {
+                    -VOLUME_STEP
+                };
+                ui_state.volume = (ui_state.volume + step).clamp(0.0, VOLUME_MAX);
+                player_evt.send(PlayerEvent::Volume(ui_state.volume));
+            }
+            Action::ToggleFullscreen => player_evt.send(PlayerEvent::Fullscreen),
+            Action::LeaveFullscreen => {
+                if ui_state.window_mode == WindowMode::Fullscreen {
+                    player_evt.send(PlayerEvent::Fullscreen);
+                }
+            }
+            Action::ToggleMute => player_evt.send(PlayerEvent::Mute(!ui_state.mute)),
+            Action::Next => player_evt.send(PlayerEvent::Next),
+            Action::Previous => player_evt.send(PlayerEvent::Previous),
+            Action::Screenshot => player_evt.send(PlayerEvent::Screenshot),
+            Action::ZoomIn => ui_state.zoom.zoom(ZOOM_STEP, None),
+            Action::ZoomOut => ui_state.zoom.zoom(1.0 / ZOOM_STEP, None),
+            Action::ResetZoom => ui_state.zoom.reset(),
+            Action::AudioDelayUp | Action::AudioDelayDown => {
+                if ui_state.playing {
+                    let step = if action == Action::AudioDelayUp {
+                        AUDIO_DELAY_STEP
+                    } else {
+                        -AUDIO_DELAY_STEP
+                    };
+                    let delay = ui_state.file_settings.audio_delay + step;
+                    player_evt.send(PlayerEvent::AudioDelay(delay));
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
+pub mod drop_system;
 pub mod event_system;
+pub mod input_system;
 pub mod play_system;
+pub mod setting_system;
 pub mod setup_system;
 pub mod ui_system;
 
@@ -1,17 +1,25 @@
 use bevy::prelude::*;
 
 use crate::{
-    resources::event::PlayerEvent,
-    services::player::{player::Player, PlayState},
+    resources::{event::PlayerEvent, playlist::PlayItemState},
+    services::{
+        history::WatchHistory,
+        player::{player::Player, PlayState},
+    },
     system::GameState,
-    ui::ui_state::UiState,
+    ui::{notice_overlay::Notice, ui_state::UiState},
 };
 
 pub fn start_player(mut ui_state: ResMut<UiState>) {
     ui_state.enter_playing();
 }
 
-pub fn stop_player(mut ui_state: ResMut<UiState>, player: ResMut<Player>) {
+pub fn stop_player(
+    mut ui_state: ResMut<UiState>,
+    player: ResMut<Player>,
+    mut history: ResMut<WatchHistory>,
+) {
+    history.save();
     ui_state.exit_playing();
     player.set_play_finished();
     // log::info!("service - state: {:?}", &ui_state.play_state);
@@ -22,7 +30,27 @@ pub fn update_player(
     mut game_state: ResMut<State<GameState>>,
     mut player: ResMut<Player>,
     mut play_evt_sender: EventWriter<PlayerEvent>,
+    mut history: ResMut<WatchHistory>,
 ) {
+    // 记录播放位置, 还没有播放出任何一帧时, 位置是 0
+    if let (Some(item), Some(position)) = (ui_state.playlist.current(), player.position()) {
+        if item.state == PlayItemState::Playing && position > 0.0 {
+            history.update(&item.path, position, item.duration);
+        }
+    }
+    history.save_if_needed();
+    if let Some(frame_counts) = player.frame_counts() {
+        ui_state.frame_counts = frame_counts;
+    }
+    if let Some(audio_devices) = player.audio_devices() {
+        ui_state.audio_devices = audio_devices;
+    }
+    if !ui_state.has_video {
+        if let Some((samples, sample_rate)) = player.audio_samples() {
+            ui_state.visualizer.set_samples(samples, sample_rate);
+        }
+    }
+
     // 更新 状态
     if let Some(state) = player.try_recv_state() {
         // log::info!("service - state: {:?}", &state);
@@ -30,10 +58,40 @@ pub fn update_player(
             PlayState::Pausing(pause) => {
                 ui_state.pause = pause;
             }
+            PlayState::AudioDeviceLost(name) => {
+                // 播放器已经切换到默认设备, 设置中保留选择的设备, 重新连接时切换回来
+                ui_state.audio_device = None;
+                ui_state.notice = Some(Notice::new(format!(
+                    "音频设备 {} 不可用, 已切换到默认设备",
+                    name
+                )));
+            }
+            PlayState::AudioDeviceRestored(name) => {
+                ui_state.audio_device = Some(name.clone());
+                ui_state.notice = Some(Notice::new(format!("已切换回音频设备 {}", name)));
+            }
+            PlayState::FilterError(e) => {
+                ui_state.notice = Some(Notice::new(format!("视频滤镜无效, 没有生效: {}", e)));
+            }
             PlayState::Terminated => {
                 game_state.set(GameState::Terminal).ok();
             }
             PlayState::Video(video) => {
+                // 视频的分辨率改变时, 重新通知播放器画面的显示大小
+                if let Some(last) = &ui_state.video {
+                    if (last.native_width, last.native_height)
+                        != (video.native_width, video.native_height)
+                    {
+                        log::info!(
+                            "视频分辨率改变: {}x{} -> {}x{}",
+                            last.native_width,
+                            last.native_height,
+                            video.native_width,
+                            video.native_height
+                        );
+                        ui_state.display_size = None;
+                    }
+                }
                 ui_state.video = Some(video);
                 // game_state.set(GameState::Terminal).ok();
             }
@@ -42,13 +100,10 @@ pub fn update_player(
             }
         }
     }
-    // 如果一个文件已经播放完毕
-    else if player.play_finished() {
-        if ui_state.looping {
-            play_evt_sender.send(PlayerEvent::Next);
-        } else {
-            game_state.set(GameState::Terminal).ok();
-        }
+    // 如果一个文件已经播放完毕, 由播放列表根据循环模式决定是否继续播放
+    // 正在询问是否继续播放时, 等待用户选择
+    else if player.play_finished() && ui_state.resume_prompt.is_none() {
+        play_evt_sender.send(PlayerEvent::AutoNext);
     }
 }
 
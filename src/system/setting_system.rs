@@ -0,0 +1,88 @@
+use std::time::Instant;
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, EguiSettings};
+
+use crate::{
+    defines::SETTINGS_SAVE_DELAY,
+    services::{player::player::Player, settings::Settings},
+    ui::ui_state::UiState,
+};
+
+/// 设置发生变化后, 立即应用, 停止修改 SETTINGS_SAVE_DELAY 后保存到配置文件
+///
+/// egui 的控件每一帧都会可变借用 Settings, 所以不能依赖 bevy 的变化检测,
+/// 而是与上一次应用的设置进行比较
+pub fn update_settings(
+    settings: Res<Settings>,
+    mut applied: Local<Option<Settings>>,
+    mut changed_at: Local<Option<Instant>>,
+    mut egui_ctx: ResMut<EguiContext>,
+    mut egui_settings: ResMut<EguiSettings>,
+    mut ui_state: ResMut<UiState>,
+    player: Res<Player>,
+) {
+    // 拖动滑块 或 输入文字时每一帧都会修改, 等修改停止后再保存
+    if let Some(time) = *changed_at {
+        if time.elapsed() >= SETTINGS_SAVE_DELAY {
+            settings.save();
+            *changed_at = None;
+        }
+    }
+
+    // 第一次运行时, 启动阶段已经应用过了
+    let last = match applied.as_ref() {
+        Some(last) if last == &*settings => return,
+        Some(last) => last,
+        None => {
+            *applied = Some(settings.clone());
+            return;
+        }
+    };
+
+    if last.volume != settings.volume {
+        ui_state.volume = settings.volume;
+        player.set_volume(settings.volume);
+    }
+    // 启动时窗口 只在下次启动时生效, 不需要应用
+    if last.scale_factor != settings.scale_factor {
+        egui_settings.scale_factor = settings.scale_factor;
+    }
+    if last.theme != settings.theme && ui_state.theme.select(&settings.theme) {
+        egui_ctx.ctx_mut().set_style(ui_state.theme.style_clone());
+    }
+    if last.repeat != settings.repeat {
+        ui_state.playlist.set_repeat(settings.repeat);
+    }
+    if last.visualizer != settings.visualizer {
+        ui_state.visualizer.style = settings.visualizer;
+    }
+    if last.video_filter != settings.video_filter {
+        player.set_video_filter(settings.video_filter.filter());
+    }
+    if last.scaler != settings.scaler {
+        player.set_scaler(settings.scaler);
+    }
+    if last.loudness != settings.loudness {
+        player.set_loudness(settings.loudness);
+    }
+    if last.downmix != settings.downmix {
+        player.set_downmix(settings.downmix.clone());
+    }
+    if last.equalizer.active() != settings.equalizer.active() {
+        player.set_equalizer(settings.equalizer.active());
+    }
+    if last.audio_device != settings.audio_device {
+        ui_state.audio_device = settings.audio_device.clone();
+        player.set_audio_device(settings.audio_device.clone());
+    }
+    if last.tone_mapping != settings.tone_mapping {
+        player.set_tone_mapping(settings.tone_mapping);
+    }
+    if last.color_space != settings.color_space {
+        player.set_color_space(settings.color_space);
+    }
+
+    *changed_at = Some(Instant::now());
+    *applied = Some(settings.clone());
+}
@@ -2,27 +2,34 @@ use bevy::{prelude::*, window::WindowId, winit::WinitWindows};
 use bevy_egui::{EguiContext, EguiSettings};
 use winit::window::Icon;
 
-use crate::{defines::icons::ICON_LOGO, resources::fonts::load_fonts, ui::ui_state::UiState};
+use crate::{
+    defines::icons::ICON_LOGO, resources::fonts::load_fonts, services::settings::Settings,
+    ui::ui_state::UiState,
+};
 
 /// egui环境初始化
 pub fn egui_setup(
     mut egui_ctx: ResMut<EguiContext>,
     mut egui_settings: ResMut<EguiSettings>,
     mut windows: ResMut<Windows>,
-    ui_state: Res<UiState>,
+    mut ui_state: ResMut<UiState>,
+    settings: Res<Settings>,
 ) {
     if let Some(window) = windows.get_primary_mut() {
         window.set_maximized(ui_state.maximized);
         window.set_mode(ui_state.window_mode);
     }
 
-    egui_settings.scale_factor = ui_state.scale_factor;
+    egui_settings.scale_factor = settings.scale_factor;
 
     let ctx = egui_ctx.ctx_mut();
 
     ctx.set_fonts(load_fonts());
 
-    ctx.set_style(ui_state.theme.blue_style_clone());
+    if !ui_state.theme.select(&settings.theme) {
+        log::warn!("主题不存在: {}", settings.theme);
+    }
+    ctx.set_style(ui_state.theme.style_clone());
 
     // ctx.set_debug_on_hover(true);
 }
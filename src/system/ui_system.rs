@@ -1,13 +1,15 @@
 use bevy::prelude::*;
 use bevy_egui::{
-    egui::{style::Margin, CentralPanel, Color32, Frame, TopBottomPanel},
+    egui::{style::Margin, CentralPanel, Frame, TopBottomPanel},
     EguiContext,
 };
 
 use crate::{
     resources::event::PlayerEvent,
+    services::settings::Settings,
     ui::{
-        play_content::PlayContentView, play_control::VideoControl, play_list::VideoListView,
+        drop_overlay::DropOverlay, notice_overlay::NoticeOverlay, play_content::PlayContentView,
+        play_control::VideoControl, play_list::VideoListView, resume_ui::ResumeWindow,
         titlebar_ui::Titlebar, ui_state::UiState,
     },
 };
@@ -18,18 +20,18 @@ pub fn update_ui(
     mut egui_ctx: ResMut<EguiContext>,
     mut ui_state: ResMut<UiState>,
     mut player_event: EventWriter<PlayerEvent>,
+    mut settings: ResMut<Settings>,
 ) {
     let ctx = egui_ctx.ctx_mut();
     let ui_state = &mut *ui_state;
 
-    TopBottomPanel::top("top_panel").show(ctx, |ui| {
-        Titlebar::show(ctx, ui, ui_state, &mut player_event)
-    });
+    TopBottomPanel::top("top_panel")
+        .show(ctx, |ui| Titlebar::show(ui, ui_state, &mut player_event));
 
     // 设置背景
     let frame = Frame {
         inner_margin: Margin::symmetric(0.0, 6.0),
-        fill: Color32::from_rgb(42, 56, 115),
+        fill: ui_state.theme.control_bar_bg(),
         ..Default::default()
     };
 
@@ -50,8 +52,29 @@ pub fn update_ui(
     };
 
     CentralPanel::default().frame(frame).show(ctx, |ui| {
-        PlayContentView::show(ctx, ui, ui_state);
+        PlayContentView::show(ctx, ui, ui_state, &mut player_event);
     });
 
-    ui_state.setting_window.show(ctx);
+    ui_state.setting_window.show(
+        ctx,
+        &mut settings,
+        &ui_state.theme,
+        ui_state.file_settings.audio_delay,
+        &mut player_event,
+    );
+    ui_state
+        .theme_window
+        .show(ctx, &mut ui_state.theme, &mut settings);
+
+    ui_state
+        .color_window
+        .show(ctx, &ui_state.file_settings.color, &mut player_event);
+
+    ui_state.equalizer_window.show(ctx, &mut settings.equalizer);
+
+    ResumeWindow::show(ctx, ui_state, &mut player_event);
+
+    NoticeOverlay::show(ctx, ui_state);
+
+    DropOverlay::show(ctx, ui_state);
 }
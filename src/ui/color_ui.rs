@@ -0,0 +1,66 @@
+use bevy::prelude::EventWriter;
+use bevy_egui::egui::{Align2, Context, Grid, Slider, Window};
+
+use crate::{resources::event::PlayerEvent, services::player::ColorAdjust};
+
+/// 色彩调节, 修改后立即生效, 并保存为当前文件的设置
+#[derive(Default)]
+pub struct ColorWindow {
+    /// 控制窗口显示
+    open: bool,
+}
+
+impl ColorWindow {
+    pub fn trigger_show(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        color: &ColorAdjust,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
+        let mut open = self.open;
+        Window::new("色彩调节")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open)
+            .anchor(Align2::RIGHT_CENTER, [-20.0, 0.0])
+            .show(ctx, |ui| {
+                let mut new_color = *color;
+                Grid::new("color_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("亮度");
+                        ui.add(Slider::new(&mut new_color.brightness, -1.0..=1.0));
+                        ui.end_row();
+
+                        ui.label("对比度");
+                        ui.add(Slider::new(&mut new_color.contrast, 0.0..=2.0));
+                        ui.end_row();
+
+                        ui.label("饱和度");
+                        ui.add(Slider::new(&mut new_color.saturation, 0.0..=3.0));
+                        ui.end_row();
+
+                        ui.label("伽马");
+                        ui.add(Slider::new(&mut new_color.gamma, 0.1..=3.0));
+                        ui.end_row();
+
+                        ui.label("色相");
+                        ui.add(Slider::new(&mut new_color.hue, -180.0..=180.0).suffix("°"));
+                        ui.end_row();
+                    });
+
+                if ui.button("重置").clicked() {
+                    new_color = ColorAdjust::default();
+                }
+                if &new_color != color {
+                    player_evt.send(PlayerEvent::Color(new_color));
+                }
+            });
+        self.open = open;
+    }
+}
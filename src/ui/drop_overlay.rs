@@ -0,0 +1,42 @@
+use bevy_egui::egui::{vec2, Align2, Color32, Context, FontId, Id, LayerId, Order, Stroke};
+
+use super::ui_state::UiState;
+
+/// 文件拖拽到窗口上方时, 显示的放置区域提示
+pub struct DropOverlay {}
+
+impl DropOverlay {
+    pub fn show(ctx: &Context, ui_state: &UiState) {
+        if !ui_state.drop_hovering {
+            return;
+        }
+
+        let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("drop_overlay")));
+        let screen_rect = ctx.input().screen_rect();
+        let highlight = Color32::from_rgb(4, 148, 210);
+
+        painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(120));
+        painter.rect_stroke(screen_rect.shrink(2.0), 0.0, Stroke::new(2.0, highlight));
+        painter.text(
+            screen_rect.center_top() + vec2(0.0, 40.0),
+            Align2::CENTER_CENTER,
+            "释放以添加到播放列表",
+            FontId::proportional(20.0),
+            Color32::WHITE,
+        );
+
+        // 视频区域
+        let video_rect = ui_state.video_rect;
+        if video_rect.is_positive() {
+            painter.rect_filled(video_rect.shrink(8.0), 4.0, highlight.linear_multiply(0.25));
+            painter.rect_stroke(video_rect.shrink(8.0), 4.0, Stroke::new(3.0, highlight));
+            painter.text(
+                video_rect.center(),
+                Align2::CENTER_CENTER,
+                "释放到这里立即播放",
+                FontId::proportional(28.0),
+                Color32::WHITE,
+            );
+        }
+    }
+}
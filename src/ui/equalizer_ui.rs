@@ -0,0 +1,131 @@
+use bevy_egui::egui::{Align2, Button, ComboBox, Context, Slider, TextEdit, Window};
+
+use crate::services::{
+    player::Equalizer,
+    settings::{EqualizerPreset, EqualizerSettings},
+};
+
+/// 均衡器, 修改后立即生效, 并保存到设置中
+#[derive(Default)]
+pub struct EqualizerWindow {
+    /// 控制窗口显示
+    open: bool,
+    /// 标记着 控制窗口第一次打开, 只在打开时固定位置, 之后可以拖动
+    first_open: bool,
+    frame: u32,
+    /// 保存预设时使用的名字
+    preset_name: String,
+}
+
+impl EqualizerWindow {
+    pub fn trigger_show(&mut self) {
+        self.open = !self.open;
+        if self.open {
+            self.first_open = true;
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, settings: &mut EqualizerSettings) {
+        self.frame = self.frame.wrapping_add(1);
+
+        let mut open = self.open;
+        let window = Window::new("均衡器")
+            .collapsible(false)
+            .resizable(false)
+            .open(&mut open);
+        // 第一次显示时窗口的大小还不确定, 前两帧都需要固定位置
+        let window = if self.frame <= 2 || self.first_open {
+            self.first_open = false;
+            window.anchor(Align2::CENTER_BOTTOM, [0.0, -80.0])
+        } else {
+            window
+        };
+        window.show(ctx, |ui| {
+            let builtin = EqualizerPreset::builtin();
+            // 和当前增益相同的预设
+            let current = builtin
+                .iter()
+                .chain(settings.presets.iter())
+                .find(|preset| preset.equalizer == settings.equalizer)
+                .map(|preset| preset.name.clone());
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.enabled, "启用");
+                ComboBox::from_id_source("equalizer_preset")
+                    .selected_text(current.as_deref().unwrap_or("自定义"))
+                    .show_ui(ui, |ui| {
+                        for preset in builtin.iter().chain(settings.presets.iter()) {
+                            let selected = current.as_ref() == Some(&preset.name);
+                            if ui.selectable_label(selected, &preset.name).clicked() {
+                                settings.equalizer = preset.equalizer;
+                            }
+                        }
+                    });
+                if ui.button("重置").clicked() {
+                    settings.equalizer = Equalizer::default();
+                }
+            });
+
+            ui.add_enabled_ui(settings.enabled, |ui| {
+                ui.horizontal(|ui| {
+                    let bands = Equalizer::FREQUENCIES
+                        .iter()
+                        .zip(settings.equalizer.gains.iter_mut());
+                    for (frequency, gain) in bands {
+                        ui.vertical(|ui| {
+                            ui.add(
+                                Slider::new(gain, -Equalizer::MAX_GAIN..=Equalizer::MAX_GAIN)
+                                    .vertical()
+                                    .step_by(0.5)
+                                    .show_value(false),
+                            );
+                            ui.label(format_frequency(*frequency))
+                                .on_hover_text(format!("{:+.1} dB", gain));
+                        });
+                    }
+                });
+            });
+
+            // 保存为预设, 名字相同时覆盖, 内置预设不能覆盖或删除
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.preset_name)
+                        .hint_text("预设名")
+                        .desired_width(120.0),
+                );
+                let name = self.preset_name.trim().to_string();
+                let is_builtin = builtin.iter().any(|preset| preset.name == name);
+                let can_save = !name.is_empty() && !is_builtin;
+                if ui.add_enabled(can_save, Button::new("保存")).clicked() {
+                    let preset = EqualizerPreset {
+                        name: name.clone(),
+                        equalizer: settings.equalizer,
+                    };
+                    match settings.presets.iter_mut().find(|p| p.name == name) {
+                        Some(existing) => *existing = preset,
+                        None => settings.presets.push(preset),
+                    }
+                }
+                let custom = settings
+                    .presets
+                    .iter()
+                    .position(|preset| Some(&preset.name) == current.as_ref());
+                if let Some(index) = custom {
+                    if ui.button("删除预设").clicked() {
+                        settings.presets.remove(index);
+                    }
+                }
+            });
+        });
+        self.open = open;
+    }
+}
+
+/// 31 -> "31", 1000 -> "1k"
+fn format_frequency(frequency: u32) -> String {
+    if frequency >= 1000 {
+        format!("{}k", frequency / 1000)
+    } else {
+        frequency.to_string()
+    }
+}
@@ -1,7 +1,14 @@
+pub mod color_ui;
+pub mod drop_overlay;
+pub mod equalizer_ui;
 pub mod load_icons;
+pub mod notice_overlay;
 pub mod play_content;
 pub mod play_control;
 pub mod play_list;
+pub mod resume_ui;
 pub mod setting_ui;
+pub mod theme_ui;
 pub mod titlebar_ui;
 pub mod ui_state;
+pub mod visualizer;
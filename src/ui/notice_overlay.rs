@@ -0,0 +1,54 @@
+use bevy_egui::egui::{vec2, Color32, Context, FontId, Id, LayerId, Order, Rect};
+
+use crate::defines::NOTICE_DURATION;
+
+use super::ui_state::UiState;
+
+/// 短暂显示在画面上方的提示
+pub struct Notice {
+    pub text: String,
+    /// 开始显示的时间, 第一次显示时记录
+    since: Option<f64>,
+}
+
+impl Notice {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            since: None,
+        }
+    }
+}
+
+pub struct NoticeOverlay {}
+
+impl NoticeOverlay {
+    pub fn show(ctx: &Context, ui_state: &mut UiState) {
+        let notice = match &mut ui_state.notice {
+            Some(notice) => notice,
+            None => return,
+        };
+        let now = ctx.input().time;
+        let since = *notice.since.get_or_insert(now);
+        if now - since > NOTICE_DURATION {
+            ui_state.notice = None;
+            return;
+        }
+        ctx.request_repaint();
+
+        let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("notice")));
+        let pos = ui_state.video_rect.center_top() + vec2(0.0, 40.0);
+        let galley = painter.layout_no_wrap(
+            notice.text.clone(),
+            FontId::proportional(18.0),
+            Color32::WHITE,
+        );
+        let text_rect = Rect::from_center_size(pos, galley.size());
+        painter.rect_filled(
+            text_rect.expand2(vec2(12.0, 6.0)),
+            4.0,
+            Color32::from_black_alpha(160),
+        );
+        painter.galley(text_rect.min, galley);
+    }
+}
@@ -1,50 +1,340 @@
-use bevy_egui::egui::{vec2, ColorImage, Context, InnerResponse, Sense, Ui};
+use bevy::prelude::EventWriter;
+use bevy_egui::egui::{
+    vec2, Align2, Button, Color32, ColorImage, Context, Image, Pos2, Rect, Response, Sense,
+    TextStyle, Ui,
+};
+
+use crate::{
+    defines::{
+        DOUBLE_CLICK_DELAY, PLAYBACK_SPEEDS, SCROLL_STEP, SEEK_STEP, VOLUME_MAX, VOLUME_STEP,
+    },
+    resources::{
+        display::{video_layout, AspectRatio, Rotation, ScaleMode, VisualizerStyle},
+        event::PlayerEvent,
+    },
+};
 
 use super::ui_state::UiState;
 
 pub struct PlayContentView {}
 
 impl PlayContentView {
-    pub fn show(ctx: &Context, ui: &mut Ui, ui_state: &mut UiState) {
-        ui.set_style(ui_state.theme.blue_video_content_style());
+    pub fn show(
+        ctx: &Context,
+        ui: &mut Ui,
+        ui_state: &mut UiState,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
+        ui.set_style(ui_state.theme.video_content_style());
+        ui_state.video_rect = ui.max_rect();
         // 视频状态
         if let Some(video) = &ui_state.video {
-            // 居中
-            let InnerResponse { inner: _, response } = ui.vertical_centered_justified(|ui| {
-                let width = video.width as f32;
-                let height = video.height as f32;
-
-                ui_state.video_texture = Some(ctx.load_texture(
-                    "play_content_texture",
-                    ColorImage::from_rgba_unmultiplied(
-                        [video.width, video.height],
-                        video.data.as_slice(),
-                    ),
-                ));
-
-                if let Some(texture) = &ui_state.video_texture {
-                    let w = ui.available_width();
-                    let h = ui.available_height();
-                    let img_height = w * height as f32 / width as f32;
-                    let img_size = vec2(w, img_height);
-                    let space_amount = (h - img_height) / 2.0;
-                    ui.add_space(space_amount);
-                    ui.image(texture, img_size);
-                }
-            });
+            let area = ui.max_rect();
+            let aspect_ratio = ui_state
+                .aspect_ratio
+                .ratio()
+                .unwrap_or(video.display_aspect_ratio() as f32);
+            // 原始大小: 高度方向一个视频像素对应一个屏幕像素, 宽度按宽高比换算
+            let height = video.native_height as f32 / ctx.pixels_per_point();
+            let original_size = vec2(height * aspect_ratio, height);
+
+            let texture = ctx.load_texture(
+                "play_content_texture",
+                ColorImage::from_rgba_unmultiplied(
+                    [video.width, video.height],
+                    video.data.as_slice(),
+                ),
+            );
+            let (rect, uv) = video_layout(area, aspect_ratio, ui_state.scale_mode, original_size);
+            let uv = ui_state.zoom.apply(uv);
+            Image::new(&texture, rect.size()).uv(uv).paint_at(ui, rect);
+            ui_state.video_texture = Some(texture);
+
+            // 整个画面(包括裁剪掉的部分)在屏幕上的大小, 播放器直接把视频帧缩放到这个大小
+            let picture_size = rect.size() / uv.size() * ctx.pixels_per_point();
+            let display_size = (picture_size.x.round() as i32, picture_size.y.round() as i32);
+            if display_size.0 > 0 && ui_state.display_size != Some(display_size) {
+                ui_state.display_size = Some(display_size);
+                player_evt.send(PlayerEvent::DisplaySize(display_size.0, display_size.1));
+            }
+
+            if ui_state.show_stats {
+                Self::stats_overlay(ui, area, ui_state);
+            }
+
+            let response = ui.interact(area, ui.id(), Sense::click_and_drag());
+
+            Self::handle_click(ctx, &response, ui_state, player_evt);
+            Self::handle_zoom(ui, &response, rect, uv, ui_state);
+            Self::handle_scroll(ui, &response, ui_state, player_evt);
 
-            let response = ui.interact(response.rect, ui.id(), Sense::click());
+            response.context_menu(|ui| Self::context_menu(ui, ui_state, player_evt));
+        } else if ui_state.playing && !ui_state.has_video {
+            let area = ui.max_rect();
+            ui_state.visualizer.show(ui, area);
 
-            response.context_menu(|ui| {
-                if ui.button("静音").clicked() {
+            let response = ui.interact(area, ui.id(), Sense::click());
+
+            Self::handle_click(ctx, &response, ui_state, player_evt);
+            Self::handle_scroll(ui, &response, ui_state, player_evt);
+
+            response.context_menu(|ui| Self::context_menu(ui, ui_state, player_evt));
+        } else if !ui_state.playing {
+            ui.heading("Terminated");
+        }
+    }
+
+    /// 在画面左上角显示 分辨率 和 帧统计
+    fn stats_overlay(ui: &Ui, area: Rect, ui_state: &UiState) {
+        let counts = ui_state.frame_counts;
+        let mut text = format!(
+            "解码 {}  丢弃 {}  显示 {}",
+            counts.decoded, counts.dropped, counts.presented
+        );
+        if let Some(video) = &ui_state.video {
+            text = format!(
+                "{}x{} -> {}x{}\n{}",
+                video.native_width, video.native_height, video.width, video.height, text
+            );
+        }
+        ui.painter().text(
+            area.left_top() + vec2(8.0, 8.0),
+            Align2::LEFT_TOP,
+            text,
+            TextStyle::Monospace.resolve(ui.style()),
+            Color32::WHITE,
+        );
+    }
+
+    /// 双击切换全屏, 单击暂停 / 继续播放
+    /// 单击要等到双击间隔过去之后才生效, 否则双击时会先暂停一次
+    fn handle_click(
+        ctx: &Context,
+        response: &Response,
+        ui_state: &mut UiState,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
+        let now = ctx.input().time;
+        if response.double_clicked() {
+            ui_state.pending_click = None;
+            player_evt.send(PlayerEvent::Fullscreen);
+        } else if response.clicked() {
+            ui_state.pending_click = Some(now);
+        }
+
+        if let Some(time) = ui_state.pending_click {
+            if now - time > DOUBLE_CLICK_DELAY {
+                ui_state.pending_click = None;
+                player_evt.send(PlayerEvent::Pause(!ui_state.pause));
+            } else {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    /// 按住 Ctrl 滚动滚轮(或触控板缩放)放大画面, 放大后拖动画面平移
+    ///
+    /// rect: 画面的显示位置, uv: 放大后显示的纹理区域
+    fn handle_zoom(ui: &Ui, response: &Response, rect: Rect, uv: Rect, ui_state: &mut UiState) {
+        // 屏幕上的位置 对应的 uv
+        let to_uv = |pos: Pos2| uv.min + (pos - rect.min) / rect.size() * uv.size();
+
+        if response.hovered() {
+            let input = ui.input();
+            // egui 把 Ctrl + 滚轮 和 触控板缩放 都转换为 zoom_delta
+            let factor = input.zoom_delta();
+            if factor != 1.0 {
+                let anchor = input.pointer.hover_pos().map(to_uv);
+                ui_state.zoom.zoom(factor, anchor);
+            }
+        }
+
+        if response.dragged() && ui_state.zoom.is_zoomed() {
+            ui_state
+                .zoom
+                .pan(-response.drag_delta() / rect.size() * uv.size());
+        }
+    }
+
+    /// 滚轮调节音量, 按住 Shift 时滚轮跳转
+    fn handle_scroll(
+        ui: &Ui,
+        response: &Response,
+        ui_state: &mut UiState,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
+        // 按住 Ctrl 时滚轮用于放大画面
+        if !response.hovered() || ui.input().modifiers.ctrl {
+            ui_state.scroll_delta = Default::default();
+            return;
+        }
+        // 按住 Shift 时, egui 把竖直滚动转换为水平滚动
+        ui_state.scroll_delta += ui.input().scroll_delta;
+
+        let steps = (ui_state.scroll_delta.y / SCROLL_STEP).trunc();
+        if steps != 0.0 {
+            ui_state.scroll_delta.y -= steps * SCROLL_STEP;
+            ui_state.volume = (ui_state.volume + steps * VOLUME_STEP).clamp(0.0, VOLUME_MAX);
+            player_evt.send(PlayerEvent::Volume(ui_state.volume));
+        }
+
+        let steps = (ui_state.scroll_delta.x / SCROLL_STEP).trunc();
+        if steps != 0.0 {
+            ui_state.scroll_delta.x -= steps * SCROLL_STEP;
+            player_evt.send(PlayerEvent::Seek(steps as f64 * SEEK_STEP));
+        }
+    }
+
+    fn context_menu(
+        ui: &mut Ui,
+        ui_state: &mut UiState,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
+        let pause_text = if ui_state.pause { "播放" } else { "暂停" };
+        if ui.button(pause_text).clicked() {
+            player_evt.send(PlayerEvent::Pause(!ui_state.pause));
+            ui.close_menu();
+        }
+        let mute_text = if ui_state.mute {
+            "取消静音"
+        } else {
+            "静音"
+        };
+        if ui.button(mute_text).clicked() {
+            player_evt.send(PlayerEvent::Mute(!ui_state.mute));
+            ui.close_menu();
+        }
+        if ui.button("上一首").clicked() {
+            player_evt.send(PlayerEvent::Previous);
+            ui.close_menu();
+        }
+        if ui.button("下一首").clicked() {
+            player_evt.send(PlayerEvent::Next);
+            ui.close_menu();
+        }
+        ui.separator();
+
+        ui.menu_button("音轨", |ui| {
+            if ui_state.audio_tracks.is_empty() {
+                ui.label("无音轨");
+            }
+            for (i, track) in ui_state.audio_tracks.iter().enumerate() {
+                let selected = ui_state.audio_track == Some(track.stream_idx);
+                if ui.radio(selected, track.name(i + 1)).clicked() {
+                    if !selected {
+                        player_evt.send(PlayerEvent::AudioTrack(track.stream_idx));
+                    }
                     ui.close_menu();
                 }
-                if ui.button("下一首").clicked() {
+            }
+        });
+        ui.menu_button("音频设备", |ui| {
+            if ui
+                .radio(ui_state.audio_device.is_none(), "默认设备")
+                .clicked()
+            {
+                player_evt.send(PlayerEvent::AudioDevice(None));
+                ui.close_menu();
+            }
+            for name in &ui_state.audio_devices {
+                let selected = ui_state.audio_device.as_ref() == Some(name);
+                if ui.radio(selected, name).clicked() {
+                    player_evt.send(PlayerEvent::AudioDevice(Some(name.clone())));
                     ui.close_menu();
                 }
+            }
+        });
+        ui.menu_button("播放速度", |ui| {
+            for speed in PLAYBACK_SPEEDS {
+                if ui
+                    .radio(ui_state.speed == speed, format!("{}x", speed))
+                    .clicked()
+                {
+                    player_evt.send(PlayerEvent::Speed(speed));
+                    ui.close_menu();
+                }
+            }
+        });
+        if ui.button("均衡器...").clicked() {
+            ui_state.equalizer_window.trigger_show();
+            ui.close_menu();
+        }
+
+        // 没有视频时, 不需要画面相关的菜单
+        if !ui_state.has_video {
+            ui.menu_button("可视化", |ui| {
+                for style in VisualizerStyle::ALL {
+                    if ui
+                        .radio(ui_state.visualizer.style == style, style.name())
+                        .clicked()
+                    {
+                        player_evt.send(PlayerEvent::Visualizer(style));
+                        ui.close_menu();
+                    }
+                }
             });
-        } else if !ui_state.playing {
-            ui.heading("Terminated");
+            return;
+        }
+        ui.separator();
+
+        ui.menu_button("画面比例", |ui| {
+            for aspect_ratio in AspectRatio::ALL {
+                if ui
+                    .radio(ui_state.aspect_ratio == aspect_ratio, aspect_ratio.name())
+                    .clicked()
+                {
+                    ui_state.aspect_ratio = aspect_ratio;
+                    ui.close_menu();
+                }
+            }
+        });
+        ui.menu_button("旋转", |ui| {
+            let auto = format!("自动 ({})", ui_state.media_rotation.name());
+            if ui
+                .radio(ui_state.file_settings.rotation.is_none(), auto)
+                .clicked()
+            {
+                player_evt.send(PlayerEvent::Rotation(None));
+                ui.close_menu();
+            }
+            for rotation in Rotation::ALL {
+                let selected = ui_state.file_settings.rotation == Some(rotation);
+                if ui.radio(selected, rotation.name()).clicked() {
+                    player_evt.send(PlayerEvent::Rotation(Some(rotation)));
+                    ui.close_menu();
+                }
+            }
+        });
+        ui.menu_button("缩放", |ui| {
+            for scale_mode in ScaleMode::ALL {
+                if ui
+                    .radio(ui_state.scale_mode == scale_mode, scale_mode.name())
+                    .clicked()
+                {
+                    ui_state.scale_mode = scale_mode;
+                    ui.close_menu();
+                }
+            }
+        });
+        if ui.button("色彩调节...").clicked() {
+            ui_state.color_window.trigger_show();
+            ui.close_menu();
+        }
+        if ui
+            .add_enabled(ui_state.zoom.is_zoomed(), Button::new("重置放大"))
+            .clicked()
+        {
+            ui_state.zoom.reset();
+            ui.close_menu();
+        }
+        if ui.checkbox(&mut ui_state.show_stats, "统计信息").clicked() {
+            ui.close_menu();
+        }
+        ui.separator();
+
+        if ui.button("截图").clicked() {
+            player_evt.send(PlayerEvent::Screenshot);
+            ui.close_menu();
         }
     }
 }
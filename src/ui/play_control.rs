@@ -3,7 +3,10 @@ use bevy_egui::egui::{
     vec2, Align2, Area, Color32, Context, Label, RichText, Sense, Slider, Ui, Widget,
 };
 
-use crate::resources::event::PlayerEvent;
+use crate::{
+    defines::VOLUME_MAX,
+    resources::{event::PlayerEvent, playlist::RepeatMode},
+};
 
 use super::ui_state::UiState;
 
@@ -57,12 +60,25 @@ impl VideoControl {
                     }
                     // 循环播放
                     {
-                        let mut label = RichText::new("🔃");
-                        if ui_state.looping {
+                        let repeat = ui_state.playlist.repeat();
+                        let label = match repeat {
+                            RepeatMode::Off => RichText::new("🔃"),
+                            RepeatMode::All => RichText::new("🔃").color(Color32::YELLOW),
+                            RepeatMode::One => RichText::new("🔂").color(Color32::YELLOW),
+                        };
+                        if Label::new(label).sense(Sense::click()).ui(ui).clicked() {
+                            player_evt.send(PlayerEvent::Repeat(repeat.cycle()));
+                        }
+                    }
+                    // 随机播放
+                    {
+                        let shuffle = ui_state.playlist.shuffle();
+                        let mut label = RichText::new("🔀");
+                        if shuffle {
                             label = label.color(Color32::YELLOW);
                         }
                         if Label::new(label).sense(Sense::click()).ui(ui).clicked() {
-                            ui_state.looping = !ui_state.looping;
+                            player_evt.send(PlayerEvent::Shuffle(!shuffle));
                         }
                     }
                     ui.add_space(10.);
@@ -76,7 +92,7 @@ impl VideoControl {
                         player_evt.send(PlayerEvent::Mute(mute));
                     }
                     if ui
-                        .add(Slider::new(&mut ui_state.volume, 0.0..=1.0).show_value(false))
+                        .add(Slider::new(&mut ui_state.volume, 0.0..=VOLUME_MAX).show_value(false))
                         .changed()
                     {
                         player_evt.send(PlayerEvent::Volume(ui_state.volume));
@@ -1,9 +1,10 @@
-use std::path::Path;
-
 use bevy::prelude::EventWriter;
-use bevy_egui::egui::{Align, Context, Label, Layout, Sense, SidePanel, Widget};
+use bevy_egui::egui::{Align, Color32, Context, Label, Layout, RichText, Sense, SidePanel, Widget};
 
-use crate::resources::event::PlayerEvent;
+use crate::{
+    common::utils::format_duration,
+    resources::{event::PlayerEvent, playlist::PlayItemState},
+};
 
 use super::ui_state::UiState;
 
@@ -26,33 +27,58 @@ impl VideoListView {
                 ui.with_layout(
                     Layout::top_down(Align::Min).with_cross_justify(true),
                     |ui| {
-                        let play_list = ui_state.play_list.clone();
-                        ui.label(format!("文件数: {}", play_list.len()));
+                        let playlist = &mut ui_state.playlist;
+                        if playlist.is_empty() {
+                            ui.label("播放列表为空");
+                        } else {
+                            ui.label(format!("文件数: {}", playlist.len()));
+                        }
+
+                        // 在遍历结束后再修改列表
+                        let mut play_idx = None;
+                        let mut remove_idx = None;
+                        let current_idx = playlist.current_idx();
 
-                        for (idx, video) in play_list.iter().enumerate() {
-                            let filename = Path::new(video).file_name().unwrap().to_str().unwrap();
-                            let res = Label::new(filename).sense(Sense::click()).ui(ui);
+                        for (idx, item) in playlist.items().iter().enumerate() {
+                            let mut text = item.title.clone();
+                            if let Some(duration) = item.duration {
+                                text = format!("{}  [{}]", text, format_duration(duration));
+                            }
+                            let mut text = RichText::new(text);
+                            text = match item.state {
+                                PlayItemState::Playing => text.color(Color32::YELLOW),
+                                PlayItemState::Failed => text.color(Color32::RED).strikethrough(),
+                                PlayItemState::Pending | PlayItemState::Played => text,
+                            };
+                            if current_idx == Some(idx) {
+                                text = text.underline();
+                            }
+
+                            let res = Label::new(text).sense(Sense::click()).ui(ui);
 
                             if res.double_clicked() {
-                                ui_state.current_idx = Some(idx);
-                                player_event.send(PlayerEvent::Play);
+                                play_idx = Some(idx);
                             }
 
                             res.context_menu(|ui| {
                                 if ui.button("播放").clicked() {
-                                    ui_state.current_idx = Some(idx);
-                                    // video.clone()
-                                    player_event.send(PlayerEvent::Play);
+                                    play_idx = Some(idx);
                                     ui.close_menu();
-                                    return;
                                 }
                                 if ui.button("移除").clicked() {
-                                    ui_state.play_list.retain(|v| v != video);
+                                    remove_idx = Some(idx);
                                     ui.close_menu();
-                                    return;
                                 }
                             });
                         }
+
+                        if let Some(idx) = play_idx {
+                            playlist.select(idx);
+                            player_event.send(PlayerEvent::Play);
+                        }
+                        if let Some(idx) = remove_idx {
+                            playlist.remove(idx);
+                        }
                     },
                 );
             });
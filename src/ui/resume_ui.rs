@@ -0,0 +1,40 @@
+use bevy::prelude::EventWriter;
+use bevy_egui::egui::{Align2, Context, Window};
+
+use crate::{common::utils::format_duration, resources::event::PlayerEvent};
+
+use super::ui_state::UiState;
+
+/// 询问是否从上次的位置继续播放
+pub struct ResumeWindow {}
+
+impl ResumeWindow {
+    pub fn show(ctx: &Context, ui_state: &UiState, player_event: &mut EventWriter<PlayerEvent>) {
+        let position = match ui_state.resume_prompt {
+            Some(position) => position,
+            None => return,
+        };
+        let title = ui_state
+            .playlist
+            .current()
+            .map(|item| item.title.clone())
+            .unwrap_or_default();
+
+        Window::new("继续播放")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, [0.0, -30.0])
+            .show(ctx, |ui| {
+                ui.label(title);
+                ui.label(format!("上次播放到 {}", format_duration(position)));
+                ui.horizontal(|ui| {
+                    if ui.button("继续播放").clicked() {
+                        player_event.send(PlayerEvent::Resume(true));
+                    }
+                    if ui.button("从头播放").clicked() {
+                        player_event.send(PlayerEvent::Resume(false));
+                    }
+                });
+            });
+    }
+}
@@ -1,4 +1,30 @@
-use bevy_egui::egui::{Align2, Context, Window};
+use bevy::prelude::EventWriter;
+use bevy_egui::egui::{
+    Align2, Button, CollapsingHeader, Color32, ComboBox, Context, DragValue, Grid, Key, Response,
+    RichText, Slider, TextEdit, Ui, Window,
+};
+
+use crate::{
+    defines::{AUDIO_DELAY_MAX, AUDIO_DELAY_STEP, VOLUME_MAX},
+    resources::{
+        display::{Rotation, VisualizerStyle},
+        event::PlayerEvent,
+        keymap::{Action, Keymap},
+        playlist::RepeatMode,
+        theme::Theme,
+    },
+    services::{
+        history::ResumeMode,
+        player::{
+            audio::output_devices, Downmix, DownmixMode, Loudness, LoudnessMode, ScalerQuality,
+            ToneMapAlgorithm, ToneMapMode, YuvMatrix, YuvRange,
+        },
+        settings::{Settings, StartupWindow, VideoFilterSettings},
+    },
+};
+
+/// 可选的界面缩放
+const SCALE_FACTORS: [f64; 6] = [1.0, 1.25, 1.5, 1.75, 2.0, 2.5];
 
 pub struct SettingWindow {
     /// 控制窗口显示
@@ -6,6 +32,16 @@ pub struct SettingWindow {
     /// 标记着 控制窗口第一次打开
     first_open: bool,
     frame: u32,
+    /// 正在为这个操作录制快捷键
+    recording: Option<Action>,
+    /// 正在编辑的自定义视频滤镜, 点击应用后才生效
+    custom_filter: Option<String>,
+    /// 正在拖动 / 输入的目标响度和前置放大, 松开后才应用
+    editing_loudness: Option<Loudness>,
+    /// 正在拖动 / 输入的音频延迟, 松开后才应用
+    editing_audio_delay: Option<f64>,
+    /// 可选的音频设备, 打开窗口时刷新
+    audio_devices: Vec<String>,
 }
 
 impl Default for SettingWindow {
@@ -14,21 +50,32 @@ impl Default for SettingWindow {
             open: false,
             first_open: true,
             frame: 0,
+            recording: None,
+            custom_filter: None,
+            editing_loudness: None,
+            editing_audio_delay: None,
+            audio_devices: Vec::new(),
         }
     }
 }
 
 impl SettingWindow {
-    pub fn show(&mut self, ctx: &Context) {
+    /// audio_delay 是当前文件的音频延迟, 修改后通过 PlayerEvent 应用并保存到观看记录
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        settings: &mut Settings,
+        theme: &Theme,
+        audio_delay: f64,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
         if !self.open {
             return;
         }
 
         self.frame = self.frame.wrapping_add(1);
 
-        let window = Window::new("setting")
-            .collapsible(false)
-            .open(&mut self.open);
+        let window = Window::new("设置").collapsible(false).open(&mut self.open);
         // 如果是第一次打开, 设置居中
         // self.frame <= 2， 使用条件的原因大概是: 第一次显示这个 window, 初始位置是不确定的. 执行两次 anchor 后才可以确定
         let window = if self.frame <= 2 || self.first_open {
@@ -39,16 +86,439 @@ impl SettingWindow {
         };
 
         window.show(ctx, |ui| {
-            ui.horizontal(|ui| {
-                ui.label("settings");
+            Grid::new("setting_grid")
+                .num_columns(2)
+                .spacing([20.0, 8.0])
+                .show(ui, |ui| {
+                    Self::general_ui(ui, settings, theme);
+                    Self::play_ui(ui, settings);
+                    Self::audio_device_ui(ui, settings, &self.audio_devices);
+                    Self::loudness_ui(ui, &mut settings.loudness, &mut self.editing_loudness);
+                    Self::downmix_ui(ui, &mut settings.downmix);
+                    Self::audio_delay_ui(
+                        ui,
+                        audio_delay,
+                        &mut self.editing_audio_delay,
+                        player_evt,
+                    );
+                    Self::buffer_ui(ui, settings);
+                });
+
+            CollapsingHeader::new("视频滤镜").show(ui, |ui| {
+                Self::video_filter_ui(ui, &mut settings.video_filter, &mut self.custom_filter);
+            });
+
+            CollapsingHeader::new("快捷键").show(ui, |ui| {
+                Self::keymap_ui(ui, &mut settings.keymap, &mut self.recording);
+            });
+
+            CollapsingHeader::new("调试").show(ui, |ui| {
+                Self::debug_ui(ui, settings);
+            });
+        });
+    }
+
+    /// 正在录制快捷键的操作
+    pub fn recording(&self) -> Option<Action> {
+        self.recording
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recording = None;
+    }
+
+    /// 点击按键移除绑定, 与其他操作冲突的按键显示为红色
+    fn keymap_ui(ui: &mut Ui, keymap: &mut Keymap, recording: &mut Option<Action>) {
+        let mut unbind = None;
+
+        Grid::new("keymap_grid")
+            .num_columns(2)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                for action in Action::ALL {
+                    ui.label(action.name());
+                    ui.horizontal(|ui| {
+                        for binding in keymap.keys(action) {
+                            let conflicts = keymap.conflicts(action, binding);
+                            let mut text = RichText::new(binding.to_string());
+                            let hover = if conflicts.is_empty() {
+                                "点击移除".to_string()
+                            } else {
+                                text = text.color(Color32::RED);
+                                let names: Vec<_> = conflicts.iter().map(|a| a.name()).collect();
+                                format!("与 {} 冲突, 点击移除", names.join(", "))
+                            };
+                            if ui.button(text).on_hover_text(hover).clicked() {
+                                unbind = Some((action, *binding));
+                            }
+                        }
+
+                        let is_recording = *recording == Some(action);
+                        let label = if is_recording { "请按键..." } else { "+" };
+                        if ui.add(Button::new(label).small()).clicked() {
+                            *recording = if is_recording { None } else { Some(action) };
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        if let Some((action, binding)) = unbind {
+            keymap.unbind(action, &binding);
+        }
+        if ui.button("恢复默认").clicked() {
+            *keymap = Keymap::default();
+            *recording = None;
+        }
+    }
+
+    /// 界面
+    fn general_ui(ui: &mut Ui, settings: &mut Settings, theme: &Theme) {
+        ui.label("启动时窗口");
+        ComboBox::from_id_source("startup_window")
+            .selected_text(settings.startup_window.name())
+            .show_ui(ui, |ui| {
+                for mode in StartupWindow::ALL {
+                    ui.selectable_value(&mut settings.startup_window, mode, mode.name());
+                }
+            });
+        ui.end_row();
+
+        ui.label("界面缩放");
+        ComboBox::from_id_source("scale_factor")
+            .selected_text(format!("{}%", settings.scale_factor * 100.0))
+            .show_ui(ui, |ui| {
+                for scale in SCALE_FACTORS {
+                    let text = format!("{}%", scale * 100.0);
+                    ui.selectable_value(&mut settings.scale_factor, scale, text);
+                }
+            });
+        ui.end_row();
+
+        ui.label("主题");
+        ComboBox::from_id_source("theme")
+            .selected_text(settings.theme.as_str())
+            .show_ui(ui, |ui| {
+                for name in theme.names() {
+                    ui.selectable_value(&mut settings.theme, name.to_string(), name);
+                }
+            });
+        ui.end_row();
+
+        ui.label("打开文件的目录");
+        ui.horizontal(|ui| {
+            match &settings.open_dir {
+                Some(dir) => ui.label(dir.to_string_lossy().as_ref()),
+                None => ui.label("无"),
+            };
+            if settings.open_dir.is_some() && ui.small_button("清除").clicked() {
+                settings.open_dir = None;
+            }
+        });
+        ui.end_row();
+    }
+
+    /// 播放
+    fn play_ui(ui: &mut Ui, settings: &mut Settings) {
+        ui.label("默认音量");
+        ui.add(Slider::new(&mut settings.volume, 0.0..=VOLUME_MAX));
+        ui.end_row();
+
+        ui.label("循环模式");
+        ui.horizontal(|ui| {
+            let repeat = &mut settings.repeat;
+            ui.radio_value(repeat, RepeatMode::Off, "不循环");
+            ui.radio_value(repeat, RepeatMode::All, "列表循环");
+            ui.radio_value(repeat, RepeatMode::One, "单曲循环");
+        });
+        ui.end_row();
+
+        ui.label("继续播放");
+        ui.horizontal(|ui| {
+            let resume_mode = &mut settings.resume_mode;
+            ui.radio_value(resume_mode, ResumeMode::Ask, "询问");
+            ui.radio_value(resume_mode, ResumeMode::Always, "自动继续");
+            ui.radio_value(resume_mode, ResumeMode::Never, "从头播放");
+        });
+        ui.end_row();
+
+        ui.label("音轨语言");
+        ui.add(TextEdit::singleline(&mut settings.audio_languages).hint_text("例如: chi,eng"));
+        ui.end_row();
+
+        ui.label("字幕语言");
+        ui.add(TextEdit::singleline(&mut settings.subtitle_languages).hint_text("例如: chi,eng"));
+        ui.end_row();
+
+        ui.label("缩放算法");
+        ComboBox::from_id_source("scaler")
+            .selected_text(settings.scaler.name())
+            .show_ui(ui, |ui| {
+                for scaler in ScalerQuality::ALL {
+                    ui.selectable_value(&mut settings.scaler, scaler, scaler.name());
+                }
             });
+        ui.end_row();
+
+        ui.label("音频可视化");
+        ComboBox::from_id_source("visualizer")
+            .selected_text(settings.visualizer.name())
+            .show_ui(ui, |ui| {
+                for style in VisualizerStyle::ALL {
+                    ui.selectable_value(&mut settings.visualizer, style, style.name());
+                }
+            });
+        ui.end_row();
+
+        ui.label("HDR 色调映射");
+        ui.horizontal(|ui| {
+            let tone_mapping = &mut settings.tone_mapping;
+            for mode in ToneMapMode::ALL {
+                ui.radio_value(&mut tone_mapping.mode, mode, mode.name());
+            }
+            ui.add_enabled_ui(tone_mapping.mode != ToneMapMode::Off, |ui| {
+                ComboBox::from_id_source("tone_map_algorithm")
+                    .selected_text(tone_mapping.algorithm.name())
+                    .show_ui(ui, |ui| {
+                        for algorithm in ToneMapAlgorithm::ALL {
+                            ui.selectable_value(
+                                &mut tone_mapping.algorithm,
+                                algorithm,
+                                algorithm.name(),
+                            );
+                        }
+                    });
+            });
+        });
+        ui.end_row();
+    }
+
+    /// 音频设备, 修改后立即切换
+    fn audio_device_ui(ui: &mut Ui, settings: &mut Settings, audio_devices: &[String]) {
+        ui.label("音频设备");
+        let selected = settings.audio_device.as_deref().unwrap_or("默认设备");
+        ComboBox::from_id_source("audio_device")
+            .selected_text(selected)
+            .width(200.0)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut settings.audio_device, None, "默认设备");
+                for name in audio_devices {
+                    ui.selectable_value(&mut settings.audio_device, Some(name.clone()), name);
+                }
+            });
+        ui.end_row();
+    }
+
+    /// 声道混合, 修改后立即应用
+    fn downmix_ui(ui: &mut Ui, downmix: &mut Downmix) {
+        ui.label("声道混合");
+        ComboBox::from_id_source("downmix_mode")
+            .selected_text(downmix.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in DownmixMode::ALL {
+                    ui.selectable_value(&mut downmix.mode, mode, mode.name());
+                }
+            });
+        ui.end_row();
+
+        if downmix.mode == DownmixMode::Headphone {
+            ui.label("SOFA 文件");
+            ui.add(
+                TextEdit::singleline(&mut downmix.sofa_file)
+                    .hint_text("为空时使用交叉馈送")
+                    .desired_width(200.0),
+            );
+            ui.end_row();
+        }
+    }
+
+    /// 当前文件的音频延迟
+    /// 音频延迟在拖动结束 或 输入完成后应用, 避免拖动时每帧都保存观看记录
+    fn audio_delay_ui(
+        ui: &mut Ui,
+        audio_delay: f64,
+        editing: &mut Option<f64>,
+        player_evt: &mut EventWriter<PlayerEvent>,
+    ) {
+        ui.label("音频延迟");
+        ui.horizontal(|ui| {
+            let delay = editing.get_or_insert(audio_delay);
+            let res = ui
+                .add(
+                    DragValue::new(delay)
+                        .clamp_range(-AUDIO_DELAY_MAX..=AUDIO_DELAY_MAX)
+                        .speed(AUDIO_DELAY_STEP)
+                        .fixed_decimals(2)
+                        .suffix(" s"),
+                )
+                .on_hover_text("只对当前文件生效, 正数表示声音比画面晚");
+            if (res.drag_released() || res.lost_focus()) && *delay != audio_delay {
+                player_evt.send(PlayerEvent::AudioDelay(*delay));
+            }
+            // 不在编辑时 丢弃副本, 下一帧重新从当前文件读取
+            if !(res.dragged() || res.has_focus()) {
+                *editing = None;
+            }
+            if ui
+                .add_enabled(audio_delay != 0.0, Button::new("重置"))
+                .clicked()
+            {
+                player_evt.send(PlayerEvent::AudioDelay(0.0));
+            }
         });
+        ui.end_row();
+    }
+
+    /// 响度均衡, 修改模式后立即应用, 目标响度和前置放大在拖动结束 或 输入完成后应用,
+    /// 避免拖动时每一帧都重建音频滤镜
+    fn loudness_ui(ui: &mut Ui, loudness: &mut Loudness, editing: &mut Option<Loudness>) {
+        ui.label("响度均衡");
+        ComboBox::from_id_source("loudness_mode")
+            .selected_text(loudness.mode.name())
+            .show_ui(ui, |ui| {
+                for mode in LoudnessMode::ALL {
+                    ui.selectable_value(&mut loudness.mode, mode, mode.name());
+                }
+            });
+        ui.end_row();
+
+        ui.label("");
+        let edit = editing.get_or_insert(*loudness);
+        let (target, preamp) = ui
+            .add_enabled_ui(loudness.mode != LoudnessMode::Off, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("目标响度");
+                    let target = ui.add(
+                        DragValue::new(&mut edit.target)
+                            .clamp_range(-30.0..=-5.0)
+                            .speed(0.1)
+                            .suffix(" LUFS"),
+                    );
+                    ui.label("前置放大");
+                    let preamp = ui.add(
+                        DragValue::new(&mut edit.preamp)
+                            .clamp_range(-15.0..=15.0)
+                            .speed(0.1)
+                            .suffix(" dB"),
+                    );
+                    (target, preamp)
+                })
+                .inner
+            })
+            .inner;
+        ui.end_row();
+
+        let finished = |res: &Response| res.drag_released() || res.lost_focus();
+        if finished(&target) || finished(&preamp) {
+            loudness.target = edit.target;
+            loudness.preamp = edit.preamp;
+        }
+        // 不在编辑时 丢弃副本, 下一帧重新从设置中读取
+        let active = |res: &Response| res.dragged() || res.has_focus();
+        if !active(&target) && !active(&preamp) {
+            *editing = None;
+        }
+    }
+
+    /// 视频滤镜, 修改后立即应用
+    fn video_filter_ui(
+        ui: &mut Ui,
+        video_filter: &mut VideoFilterSettings,
+        custom_filter: &mut Option<String>,
+    ) {
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut video_filter.deinterlace, "去隔行");
+            ui.checkbox(&mut video_filter.denoise, "降噪");
+            ui.checkbox(&mut video_filter.sharpen, "锐化");
+            ui.checkbox(&mut video_filter.hflip, "水平翻转");
+            ui.checkbox(&mut video_filter.vflip, "垂直翻转");
+        });
+        ui.horizontal(|ui| {
+            ui.label("旋转");
+            for rotation in Rotation::ALL {
+                ui.radio_value(&mut video_filter.rotation, rotation, rotation.name());
+            }
+        });
+
+        // 自定义滤镜在编辑过程中可能是无效的, 所以点击应用后才生效
+        let custom = custom_filter.get_or_insert_with(|| video_filter.custom.clone());
+        ui.horizontal(|ui| {
+            ui.label("自定义");
+            let res = ui.add(TextEdit::singleline(custom).hint_text("例如: eq=gamma=1.2"));
+            let enter = res.lost_focus() && ui.input().key_pressed(Key::Enter);
+            let button = Button::new("应用");
+            let changed = *custom != video_filter.custom;
+            if ui.add_enabled(changed, button).clicked() || (enter && changed) {
+                video_filter.custom = custom.clone();
+            }
+        });
+        ui.label(RichText::new(video_filter.filter()).weak())
+            .on_hover_text("libavfilter 滤镜, 其中有无效的滤镜时 整个滤镜链都不会生效");
+    }
+
+    /// 调试选项, 修改后立即应用
+    fn debug_ui(ui: &mut Ui, settings: &mut Settings) {
+        let color_space = &mut settings.color_space;
+        Grid::new("debug_grid")
+            .num_columns(2)
+            .spacing([20.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("YUV 矩阵");
+                ComboBox::from_id_source("yuv_matrix")
+                    .selected_text(color_space.matrix.name())
+                    .show_ui(ui, |ui| {
+                        for matrix in YuvMatrix::ALL {
+                            ui.selectable_value(&mut color_space.matrix, matrix, matrix.name());
+                        }
+                    });
+                ui.end_row();
+
+                ui.label("YUV 范围");
+                ComboBox::from_id_source("yuv_range")
+                    .selected_text(color_space.range.name())
+                    .show_ui(ui, |ui| {
+                        for range in YuvRange::ALL {
+                            ui.selectable_value(&mut color_space.range, range, range.name());
+                        }
+                    });
+                ui.end_row();
+            });
+    }
+
+    /// 缓冲区, 下次打开文件时生效
+    fn buffer_ui(ui: &mut Ui, settings: &mut Settings) {
+        let buffer = &mut settings.buffer;
+
+        ui.label("音频帧队列");
+        ui.add(DragValue::new(&mut buffer.audio_frame_queue).clamp_range(1..=64));
+        ui.end_row();
+
+        ui.label("视频帧队列");
+        ui.add(DragValue::new(&mut buffer.video_frame_queue).clamp_range(1..=16));
+        ui.end_row();
+
+        ui.label("数据包队列");
+        let mut mem_mb = buffer.packet_queue_mem / 1024 / 1024;
+        let res = ui.add(
+            DragValue::new(&mut mem_mb)
+                .clamp_range(1..=512)
+                .suffix(" MB"),
+        );
+        if res.changed() {
+            buffer.packet_queue_mem = mem_mb * 1024 * 1024;
+        }
+        ui.end_row();
+
+        ui.label("");
+        ui.label("缓冲区设置在下次打开文件时生效");
+        ui.end_row();
     }
 
     pub fn trigger_show(&mut self) {
         self.open = !self.open;
         if self.open {
             self.first_open = true;
+            self.audio_devices = output_devices().unwrap_or_default();
         }
     }
 }
@@ -0,0 +1,186 @@
+use bevy_egui::egui::{
+    Align2, CollapsingHeader, Color32, ComboBox, Context, Grid, ScrollArea, TextEdit, Ui, Window,
+};
+
+use crate::{
+    resources::theme::{BaseVisuals, Theme, ThemeColors},
+    services::settings::Settings,
+};
+
+/// 主题选择和编辑
+#[derive(Default)]
+pub struct ThemeWindow {
+    /// 控制窗口显示
+    open: bool,
+    /// 标记着 控制窗口第一次打开, 只在打开时固定位置, 之后可以拖动
+    first_open: bool,
+    frame: u32,
+    /// 另存为的主题名
+    save_name: String,
+    /// 保存, 导入, 导出的结果
+    message: String,
+}
+
+impl ThemeWindow {
+    pub fn trigger_show(&mut self) {
+        self.open = !self.open;
+        if self.open {
+            self.first_open = true;
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, theme: &mut Theme, settings: &mut Settings) {
+        self.frame = self.frame.wrapping_add(1);
+
+        let mut open = self.open;
+        let window = Window::new("主题").collapsible(false).open(&mut open);
+        // 第一次显示时窗口的大小还不确定, 前两帧都需要固定位置
+        let window = if self.frame <= 2 || self.first_open {
+            self.first_open = false;
+            window.anchor(Align2::CENTER_CENTER, [0.0, -30.0])
+        } else {
+            window
+        };
+        window.show(ctx, |ui| {
+            ScrollArea::vertical().show(ui, |ui| {
+                self.picker_ui(ui, theme, settings);
+                ui.separator();
+
+                // 修改颜色后立即生效
+                let mut colors = theme.current().clone();
+                colors_ui(ui, &mut colors);
+                if &colors != theme.current() {
+                    theme.set_colors(colors);
+                    ctx.set_style(theme.style_clone());
+                }
+                ui.separator();
+
+                self.file_ui(ui, theme, settings);
+
+                CollapsingHeader::new("egui 样式").show(ui, |ui| {
+                    ctx.style_ui(ui);
+                });
+            });
+        });
+        self.open = open;
+    }
+
+    /// 选择主题
+    fn picker_ui(&mut self, ui: &mut Ui, theme: &Theme, settings: &mut Settings) {
+        ui.horizontal(|ui| {
+            ui.label("主题");
+            ComboBox::from_id_source("theme_picker")
+                .selected_text(settings.theme.as_str())
+                .show_ui(ui, |ui| {
+                    for name in theme.names() {
+                        ui.selectable_value(&mut settings.theme, name.to_string(), name);
+                    }
+                });
+        });
+    }
+
+    /// 保存, 导入, 导出
+    fn file_ui(&mut self, ui: &mut Ui, theme: &mut Theme, settings: &mut Settings) {
+        ui.horizontal(|ui| {
+            ui.add(TextEdit::singleline(&mut self.save_name).hint_text("主题名"));
+            if ui.button("另存为").clicked() {
+                let mut colors = theme.current().clone();
+                colors.name = self.save_name.trim().to_string();
+                self.save(theme, settings, colors);
+            }
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("导入").clicked() {
+                let file = rfd::FileDialog::new()
+                    .add_filter("主题", &["toml", "json"])
+                    .pick_file();
+                if let Some(file) = file {
+                    match ThemeColors::load(&file) {
+                        Ok(colors) => self.save(theme, settings, colors),
+                        Err(e) => self.message = format!("导入失败: {}", e),
+                    }
+                }
+            }
+            if ui.button("导出").clicked() {
+                let colors = theme.current();
+                let file = rfd::FileDialog::new()
+                    .add_filter("toml", &["toml"])
+                    .add_filter("json", &["json"])
+                    .set_file_name(&format!("{}.toml", colors.name))
+                    .save_file();
+                if let Some(file) = file {
+                    self.message = match colors.save(&file) {
+                        Ok(_) => format!("已导出到 {}", file.to_string_lossy()),
+                        Err(e) => format!("导出失败: {}", e),
+                    };
+                }
+            }
+        });
+
+        if !self.message.is_empty() {
+            ui.label(&self.message);
+        }
+    }
+
+    /// 保存到用户主题目录, 并切换到这个主题
+    fn save(&mut self, theme: &mut Theme, settings: &mut Settings, colors: ThemeColors) {
+        if colors.name.is_empty() {
+            self.message = "请输入主题名".to_string();
+            return;
+        }
+        if theme.is_builtin(&colors.name) {
+            self.message = format!("不能覆盖内置主题: {}", colors.name);
+            return;
+        }
+        let name = colors.name.clone();
+        self.message = match theme.save_user_theme(colors) {
+            Ok(_) => {
+                settings.theme = name.clone();
+                format!("已保存主题: {}", name)
+            }
+            Err(e) => format!("保存失败: {}", e),
+        };
+    }
+}
+
+/// 编辑主题的颜色, 没有勾选的颜色使用基础风格的颜色
+fn colors_ui(ui: &mut Ui, colors: &mut ThemeColors) {
+    ui.horizontal(|ui| {
+        ui.label("基础风格");
+        ui.radio_value(&mut colors.base, BaseVisuals::Light, "明亮");
+        ui.radio_value(&mut colors.base, BaseVisuals::Dark, "暗黑");
+    });
+
+    Grid::new("theme_colors")
+        .num_columns(2)
+        .spacing([20.0, 4.0])
+        .show(ui, |ui| {
+            let slots = [
+                ("窗口背景", &mut colors.window_bg),
+                ("窗口边框", &mut colors.window_border),
+                ("文字", &mut colors.text),
+                ("按钮文字", &mut colors.inactive_text),
+                ("按钮背景", &mut colors.inactive_bg),
+                ("鼠标经过时文字", &mut colors.hovered_text),
+                ("鼠标经过时背景", &mut colors.hovered_bg),
+                ("按下时文字", &mut colors.active_text),
+                ("按下时背景", &mut colors.active_bg),
+                ("视频区域按钮背景", &mut colors.content_button_bg),
+                ("控制栏背景", &mut colors.control_bar_bg),
+            ];
+            for (label, slot) in slots {
+                ui.label(label);
+                ui.horizontal(|ui| {
+                    let mut enabled = slot.is_some();
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        *slot = enabled.then_some(Color32::GRAY);
+                    }
+                    if let Some(color) = slot {
+                        ui.color_edit_button_srgba(color);
+                    }
+                });
+                ui.end_row();
+            }
+        });
+}
@@ -1,48 +1,21 @@
 use bevy::prelude::*;
-use bevy_egui::egui::{self, Align2, Context, Direction, Layout, ScrollArea, Sense, Window};
+use bevy_egui::egui::{self, Direction, Layout, Sense};
 
 use crate::{defines::APP_NAME, resources::event::PlayerEvent};
 
 use super::ui_state::UiState;
 
-pub struct Titlebar {
-    pub style_ui_open: bool,
-}
-
-impl Default for Titlebar {
-    fn default() -> Self {
-        Self {
-            style_ui_open: Default::default(),
-        }
-    }
-}
+pub struct Titlebar {}
 
 impl Titlebar {
-    pub fn trigger_style_ui(&mut self) {
-        self.style_ui_open = !self.style_ui_open;
-    }
-
-    pub fn style_ui(&mut self, ctx: &Context) {
-        Window::new("style_ui")
-            .collapsible(false)
-            .open(&mut self.style_ui_open)
-            .anchor(Align2::CENTER_CENTER, [0.0, -30.0])
-            .show(ctx, |ui| {
-                ScrollArea::vertical().show(ui, |ui| {
-                    ctx.style_ui(ui);
-                });
-            });
-    }
-
     pub fn show(
-        ctx: &Context,
         ui: &mut egui::Ui,
         ui_state: &mut UiState,
         player_event: &mut EventWriter<PlayerEvent>,
     ) {
         ui.horizontal(|ui| {
             // 设置 标题栏 的样式
-            ui.set_style(ui_state.theme.blue_titlebar_style_clone());
+            ui.set_style(ui_state.theme.titlebar_style_clone());
 
             ui.with_layout(egui::Layout::left_to_right(), |ui| {
                 ui.menu_button("选项", |ui| {
@@ -50,9 +23,13 @@ impl Titlebar {
                         ui.close_menu();
                         player_event.send(PlayerEvent::OpenFile);
                     }
+                    if ui.button("打开文件夹").clicked() {
+                        ui.close_menu();
+                        player_event.send(PlayerEvent::OpenFolder);
+                    }
                 });
                 ui.menu_button("样式", |ui| {
-                    ui_state.titlebar.trigger_style_ui();
+                    ui_state.theme_window.trigger_show();
                     ui.close_menu();
                 });
             });
@@ -98,7 +75,5 @@ impl Titlebar {
                 }
             });
         });
-
-        ui_state.titlebar.style_ui(ctx);
     }
 }
@@ -1,22 +1,68 @@
 use bevy::window::WindowMode;
-use bevy_egui::egui::TextureHandle;
+use bevy_egui::egui::{Rect, TextureHandle, Vec2};
 
-use super::{load_icons::Icons, setting_ui::SettingWindow, titlebar_ui::Titlebar};
-use crate::{resources::theme::Theme, services::player::video::VideoFrame};
+use super::{
+    color_ui::ColorWindow, equalizer_ui::EqualizerWindow, load_icons::Icons,
+    notice_overlay::Notice, setting_ui::SettingWindow, theme_ui::ThemeWindow,
+    visualizer::Visualizer,
+};
+use crate::{
+    resources::{
+        display::{AspectRatio, Rotation, ScaleMode, VideoZoom},
+        playlist::Playlist,
+        theme::Theme,
+    },
+    services::{
+        history::FileSettings,
+        player::{video::VideoFrame, AudioTrack, FrameCounts},
+        settings::Settings,
+    },
+};
 
 pub struct UiState {
     pub maximized: bool,
     pub window_mode: WindowMode,
-    pub scale_factor: f64,
     pub theme: Theme,
     pub icons: Icons,
-    pub titlebar: Titlebar,
     pub setting_window: SettingWindow,
-    pub play_list: Vec<String>,
-    pub current_idx: Option<usize>,
+    pub theme_window: ThemeWindow,
+    pub color_window: ColorWindow,
+    pub equalizer_window: EqualizerWindow,
+    pub playlist: Playlist,
 
     pub video: Option<VideoFrame>,
     pub video_texture: Option<TextureHandle>,
+    /// 视频区域, 用于判断文件是否拖放到了视频上
+    pub video_rect: Rect,
+    /// 强制的画面宽高比
+    pub aspect_ratio: AspectRatio,
+    /// 画面的缩放方式
+    pub scale_mode: ScaleMode,
+    /// 画面的放大和平移
+    pub zoom: VideoZoom,
+    /// 视频中记录的画面旋转
+    pub media_rotation: Rotation,
+    /// 当前文件有视频流
+    pub has_video: bool,
+    /// 没有视频时的音频可视化
+    pub visualizer: Visualizer,
+    /// 当前文件的单独设置
+    pub file_settings: FileSettings,
+    /// 单击视频的时间, 超过双击间隔后才暂停, 以便和双击区分
+    pub pending_click: Option<f64>,
+    /// 还没有处理的滚轮滚动距离
+    pub scroll_delta: Vec2,
+    /// 已通知播放器的画面显示大小(像素)
+    pub display_size: Option<(i32, i32)>,
+    /// 音频设备的名字, None 为默认设备, 与设置中的相同
+    pub audio_device: Option<String>,
+    /// 右键菜单中可选的音频设备, 打开菜单时刷新
+    pub audio_devices: Vec<String>,
+    /// 画面上方的提示
+    pub notice: Option<Notice>,
+    /// 在画面上显示帧统计
+    pub show_stats: bool,
+    pub frame_counts: FrameCounts,
 
     /// 暂停
     pub pause: bool,
@@ -24,13 +70,21 @@ pub struct UiState {
     pub volume: f32,
     /// 静音
     pub mute: bool,
-    /// 循环
-    pub looping: bool,
+    /// 播放速度
+    pub speed: f64,
+    /// 当前文件中的音轨
+    pub audio_tracks: Vec<AudioTrack>,
+    /// 正在播放的音轨(流索引)
+    pub audio_track: Option<usize>,
     /// 正在播放
     pub playing: bool,
+    /// 正在询问是否从这个位置(秒)继续播放
+    pub resume_prompt: Option<f64>,
 
     /// 打开侧边列表
     pub open_list: bool,
+    /// 有文件拖拽到窗口上方
+    pub drop_hovering: bool,
 
     /// System的FPS
     pub fps: f64,
@@ -41,27 +95,63 @@ impl Default for UiState {
         Self {
             maximized: true,
             window_mode: WindowMode::Windowed,
-            scale_factor: 1.25,
             theme: Theme::default(),
             icons: Icons::new(),
-            titlebar: Titlebar::default(),
             setting_window: Default::default(),
-            play_list: vec!["/home/maxu/Videos/trailer.mp4".to_string()],
-            current_idx: None,
+            theme_window: Default::default(),
+            color_window: Default::default(),
+            equalizer_window: Default::default(),
+            playlist: Playlist::from_paths(vec!["/home/maxu/Videos/trailer.mp4".to_string()]),
             video: None,
             video_texture: None,
+            video_rect: Rect::NOTHING,
+            aspect_ratio: AspectRatio::Auto,
+            scale_mode: ScaleMode::Fit,
+            zoom: VideoZoom::default(),
+            media_rotation: Rotation::None,
+            has_video: false,
+            visualizer: Visualizer::default(),
+            file_settings: FileSettings::default(),
+            pending_click: None,
+            scroll_delta: Vec2::ZERO,
+            display_size: None,
+            audio_device: None,
+            audio_devices: Vec::new(),
+            notice: None,
+            show_stats: false,
+            frame_counts: FrameCounts::default(),
             pause: false,
             volume: 1.0,
-            mute: true,
-            looping: false,
+            mute: false,
+            speed: 1.0,
+            audio_tracks: Vec::new(),
+            audio_track: None,
             playing: false,
+            resume_prompt: None,
             open_list: true,
+            drop_hovering: false,
             fps: 0.0,
         }
     }
 }
 
 impl UiState {
+    /// 根据用户设置, 确定启动时的状态
+    pub fn new(settings: &Settings) -> Self {
+        let mut ui_state = Self::default();
+        (ui_state.maximized, ui_state.window_mode) = settings.startup_window.window_state();
+        ui_state.volume = settings.volume;
+        ui_state.audio_device = settings.audio_device.clone();
+        ui_state.playlist.set_repeat(settings.repeat);
+        ui_state.visualizer.style = settings.visualizer;
+        ui_state
+    }
+
+    /// 实际使用的画面旋转, 手动指定的优先
+    pub fn rotation(&self) -> Rotation {
+        self.file_settings.rotation.unwrap_or(self.media_rotation)
+    }
+
     /// 进入播放状态
     pub fn enter_playing(&mut self) {
         self.playing = true;
@@ -70,42 +160,14 @@ impl UiState {
     /// 离开播放状态
     pub fn exit_playing(&mut self) {
         self.playing = false;
-
-        self.current_idx = None;
+        self.playlist.stop_playing();
         self.video = None;
         self.video_texture = None;
-    }
-}
-
-impl UiState {
-    pub fn current_filename(&mut self, offset: i32) -> Option<String> {
-        let idx;
-
-        if self.play_list.is_empty() {
-            return None;
-        }
-
-        // 如果没有选一个播放, 则设置当前选中了第一个
-        if let Some(idx_) = self.current_idx {
-            idx = idx_;
-        } else {
-            self.current_idx = Some(0);
-            return Some(self.play_list[0].clone());
-        }
-
-        // 如果不是循环
-        if !self.looping {
-            // 第一个的前一个是 None 或者 最后一个的后一个是 None
-            if (idx == 0 && offset < 0) || (idx == self.play_list.len() - 1 && offset > 0) {
-                return None;
-            }
-        }
-
-        let idx = ((idx as i32 + offset) % (self.play_list.len() as i32)) as usize;
-        if let Some(filename) = self.play_list.get(idx) {
-            self.current_idx = Some(idx);
-            return Some(filename.clone());
-        }
-        None
+        self.display_size = None;
+        self.zoom.reset();
+        self.frame_counts = FrameCounts::default();
+        self.visualizer.clear();
+        self.audio_tracks.clear();
+        self.audio_track = None;
     }
 }
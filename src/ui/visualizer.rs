@@ -0,0 +1,117 @@
+use bevy_egui::egui::{pos2, Pos2, Rect, Shape, Stroke, Ui};
+
+use crate::{common::spectrum::spectrum, resources::display::VisualizerStyle};
+
+/// 频谱分成的频段数
+const BANDS: usize = 64;
+/// 频段强度下降时, 每帧保留的比例, 上升时立即跟随
+const FALLOFF: f32 = 0.85;
+
+/// 没有视频时, 根据最近播放的音频采样显示频谱或波形
+#[derive(Default)]
+pub struct Visualizer {
+    pub style: VisualizerStyle,
+    /// 最近播放的采样(单声道)
+    samples: Vec<f32>,
+    sample_rate: u32,
+    /// 平滑后的各频段强度
+    levels: Vec<f32>,
+}
+
+impl Visualizer {
+    /// 更新最近播放的采样, 每帧调用
+    pub fn set_samples(&mut self, samples: Vec<f32>, sample_rate: u32) {
+        self.samples = samples;
+        self.sample_rate = sample_rate;
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.levels.clear();
+    }
+
+    pub fn show(&mut self, ui: &Ui, area: Rect) {
+        let rect = area.shrink2(area.size() * 0.1);
+        match self.style {
+            VisualizerStyle::Bars => {
+                self.update_levels();
+                self.bars(ui, rect);
+            }
+            VisualizerStyle::Curve => {
+                self.update_levels();
+                self.curve(ui, rect);
+            }
+            VisualizerStyle::Oscilloscope => self.oscilloscope(ui, rect),
+            VisualizerStyle::Off => {}
+        }
+    }
+
+    fn update_levels(&mut self) {
+        let levels = spectrum(&self.samples, self.sample_rate, BANDS);
+        self.levels.resize(BANDS, 0.0);
+        for (level, new) in self.levels.iter_mut().zip(levels) {
+            *level = new.max(*level * FALLOFF);
+        }
+    }
+
+    /// 频谱柱状图, 从下往上
+    fn bars(&self, ui: &Ui, rect: Rect) {
+        let color = ui.visuals().selection.bg_fill;
+        let width = rect.width() / BANDS as f32;
+        let gap = (width * 0.2).max(1.0);
+        for (i, level) in self.levels.iter().enumerate() {
+            let left = rect.left() + i as f32 * width;
+            let top = rect.bottom() - level * rect.height();
+            let bar = Rect::from_min_max(pos2(left, top), pos2(left + width - gap, rect.bottom()));
+            ui.painter().rect_filled(bar, 1.0, color);
+        }
+    }
+
+    /// 频谱曲线, 连接各频段的中点
+    fn curve(&self, ui: &Ui, rect: Rect) {
+        let color = ui.visuals().selection.bg_fill;
+        let width = rect.width() / BANDS as f32;
+        let points: Vec<Pos2> = self
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(i, level)| {
+                pos2(
+                    rect.left() + (i as f32 + 0.5) * width,
+                    rect.bottom() - level * rect.height(),
+                )
+            })
+            .collect();
+        ui.painter()
+            .add(Shape::line(points, Stroke::new(2.0, color)));
+    }
+
+    /// 示波器, 从上升的过零点开始显示, 让波形保持稳定
+    fn oscilloscope(&self, ui: &Ui, rect: Rect) {
+        let half = self.samples.len() / 2;
+        if half < 2 || rect.width() < 1.0 {
+            return;
+        }
+        let start = (1..half)
+            .find(|i| self.samples[i - 1] < 0.0 && self.samples[*i] >= 0.0)
+            .unwrap_or(0);
+        let samples = &self.samples[start..start + half];
+
+        // 每个点至少间隔 1 个像素
+        let step = (half as f32 / rect.width()).ceil().max(1.0) as usize;
+        let points: Vec<Pos2> = samples
+            .iter()
+            .enumerate()
+            .step_by(step)
+            .map(|(i, s)| {
+                pos2(
+                    rect.left() + i as f32 / half as f32 * rect.width(),
+                    rect.center().y - s.clamp(-1.0, 1.0) * rect.height() / 2.0,
+                )
+            })
+            .collect();
+        let color = ui.visuals().selection.bg_fill;
+        ui.painter()
+            .add(Shape::line(points, Stroke::new(1.5, color)));
+    }
+}